@@ -0,0 +1,173 @@
+//! Solving collections of puzzles, one puzzle per line.
+//!
+//! Puzzles are read in the line format, solved in parallel and reported in
+//! the order of the input, one tab separated line per puzzle:
+//!
+//! ```text
+//! <puzzle> <solution or -> <solution count> <milliseconds>
+//! ```
+//!
+//! The solution count stops at `solution_limit`; a puzzle with more
+//! solutions is written as for example `2+`. Puzzles that cannot be read
+//! report `error: <message>` as count. Blank lines and lines starting with
+//! `#` are skipped.
+
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+
+use super::{ChessRule, Error, RectangularBoard, Variant};
+
+/// How to read and solve the puzzles of a batch.
+#[derive(Clone, Debug)]
+pub struct BatchOptions {
+    pub block_width: usize,
+    pub block_height: usize,
+    pub variant: Variant,
+    pub chess_rules: Vec<ChessRule>,
+    /// Stop counting solutions when this many are found.
+    pub solution_limit: usize,
+    /// Number of puzzles solved in parallel before their results are written.
+    pub chunk_size: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            block_width: 3,
+            block_height: 3,
+            variant: Variant::Standard,
+            chess_rules: Vec::new(),
+            solution_limit: 2,
+            chunk_size: 1024,
+        }
+    }
+}
+
+/// The outcome of solving a single puzzle of a batch.
+#[derive(Clone, Debug)]
+pub struct BatchResult {
+    /// The 1-based line of the puzzle in the input.
+    pub line: usize,
+    pub puzzle: String,
+    /// The first solution found, in the line format.
+    pub solution: Option<String>,
+    /// The number of solutions, at most one more than `solution_limit`, to
+    /// tell exactly that many solutions from more.
+    pub solution_count: usize,
+    pub error: Option<Error>,
+    pub duration: Duration,
+}
+
+impl BatchResult {
+    fn format(&self, options: &BatchOptions) -> String {
+        let limit = options.solution_limit.max(1);
+        let count = match &self.error {
+            Some(e) => format!("error: {}", e),
+            None if self.solution_count > limit => format!("{}+", limit),
+            None => format!("{}", self.solution_count),
+        };
+        format!("{}\t{}\t{}\t{:.3}",
+                self.puzzle,
+                self.solution.as_deref().unwrap_or("-"),
+                count,
+                self.duration.as_secs_f64() * 1000.0)
+    }
+}
+
+/// Totals over all puzzles of a batch.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub puzzles: usize,
+    /// Puzzles with at least one solution.
+    pub solved: usize,
+    pub unique: usize,
+    pub multiple: usize,
+    pub unsolvable: usize,
+    pub errors: usize,
+}
+
+impl BatchSummary {
+    fn add(&mut self, result: &BatchResult) {
+        self.puzzles += 1;
+        if result.error.is_some() {
+            self.errors += 1;
+            return;
+        }
+        match result.solution_count {
+            0 => self.unsolvable += 1,
+            1 => self.unique += 1,
+            _ => self.multiple += 1,
+        }
+        if result.solution_count > 0 {
+            self.solved += 1;
+        }
+    }
+}
+
+fn read_puzzle(puzzle: &str, options: &BatchOptions) -> Result<RectangularBoard, Error> {
+    let board = RectangularBoard::from_line(puzzle, options.block_width, options.block_height)?;
+    let board = if options.variant == Variant::Standard {
+        board
+    } else {
+        board.to_variant(options.variant)?
+    };
+    Ok(options.chess_rules.iter().fold(board, |board, rule| board.with_chess_rule(*rule)))
+}
+
+/// Solve a single puzzle written in the line format.
+pub fn solve_puzzle(line: usize, puzzle: &str, options: &BatchOptions) -> BatchResult {
+    let start = Instant::now();
+    let mut result = BatchResult {
+        line,
+        puzzle: String::from(puzzle),
+        solution: None,
+        solution_count: 0,
+        error: None,
+        duration: Duration::default(),
+    };
+    match read_puzzle(puzzle, options) {
+        Ok(board) => {
+            // Search one more, to tell exactly `solution_limit` solutions from more.
+            let solutions = board.solve_up_to(options.solution_limit.max(1).saturating_add(1));
+            result.solution = solutions.first().map(|s| s.to_line());
+            result.solution_count = solutions.len();
+        },
+        Err(Error::Parse { column, message, .. }) => result.error = Some(Error::Parse { line, column, message }),
+        Err(e) => result.error = Some(e),
+    }
+    result.duration = start.elapsed();
+    result
+}
+
+fn solve_chunk<W: Write>(chunk: &mut Vec<(usize, String)>, output: &mut W, options: &BatchOptions, summary: &mut BatchSummary) -> io::Result<()> {
+    let results: Vec<BatchResult> = chunk.par_iter()
+        .map(|(line, puzzle)| solve_puzzle(*line, puzzle, options))
+        .collect();
+    for result in results {
+        summary.add(&result);
+        writeln!(output, "{}", result.format(options))?;
+    }
+    chunk.clear();
+    Ok(())
+}
+
+/// Solve all puzzles read from `input`, writing one result line per puzzle to `output`.
+pub fn solve_batch<R: BufRead, W: Write>(input: R, output: &mut W, options: &BatchOptions) -> io::Result<BatchSummary> {
+    let mut summary = BatchSummary::default();
+    let mut chunk = Vec::with_capacity(options.chunk_size);
+    for (index, l) in input.lines().enumerate() {
+        let l = l?;
+        let puzzle = l.trim();
+        if puzzle.is_empty() || puzzle.starts_with('#') {
+            continue;
+        }
+        chunk.push((index + 1, String::from(puzzle)));
+        if chunk.len() >= options.chunk_size.max(1) {
+            solve_chunk(&mut chunk, output, options, &mut summary)?;
+        }
+    }
+    solve_chunk(&mut chunk, output, options, &mut summary)?;
+    Ok(summary)
+}
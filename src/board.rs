@@ -1,21 +1,125 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 use rayon::prelude::*;
 
+use super::cage::Cage;
 use super::cell_container::CellContainer;
 use super::coord::Coord;
 use super::cell::Cell;
+use super::constraint::Constraint;
+use super::error::{Error, Result};
+use super::explain::cell_name;
 use super::group::Group;
+use super::peer_index::PeerIndex;
+use super::random::Random;
 
 #[derive(Clone)]
 pub struct Board {
     pub cells: CellContainer,
-    pub groups: Vec<Group>
+    /// All groups, including one for every cage.
+    pub groups: Arc<[Group]>,
+    /// The groups of the layout alone, without those of the cages.
+    layout_groups: Arc<[Group]>,
+    /// The killer cages, whose cells are in `groups` as well.
+    pub cages: Arc<[Cage]>,
+    /// The rules on top of the groups and cages, like chess rules.
+    constraints: Arc<[Arc<dyn Constraint>]>,
+    index: Arc<PeerIndex>,
 }
 
 impl Board {
     pub fn new(cells: CellContainer, groups: Vec<Group>) -> Self {
+        let index = PeerIndex::new(cells.group_size(), &groups);
+        let groups: Arc<[Group]> = groups.into();
         Board {
             cells,
+            layout_groups: Arc::clone(&groups),
             groups,
+            cages: Arc::new([]),
+            constraints: Arc::new([]),
+            index: Arc::new(index),
+        }
+    }
+
+    /// Create a board with killer cages on top of `groups`.
+    ///
+    /// Every cage is added as a group as well, so its values are different.
+    /// Fails when a cage does not fit the board, see `Cage::check`, or when
+    /// a cell is in more than one cage.
+    pub fn with_cages(cells: CellContainer, mut groups: Vec<Group>, cages: Vec<Cage>) -> Result<Self> {
+        let group_size = cells.group_size();
+        let mut caged = vec![false; group_size * group_size];
+        for cage in &cages {
+            cage.check(group_size)?;
+            for coord in &cage.coordinates {
+                let index = coord.y * group_size + coord.x;
+                if caged[index] {
+                    return Err(Error::Geometry {
+                        message: format!("{} is in more than one cage", cell_name(coord)),
+                    });
+                }
+                caged[index] = true;
+            }
+        }
+        let layout_groups: Arc<[Group]> = groups.as_slice().into();
+        groups.extend(cages.iter().map(|cage| Group::new(cage.coordinates.clone())));
+        Ok(Board {
+            layout_groups,
+            cages: cages.into(),
+            ..Board::new(cells, groups)
+        })
+    }
+
+    /// A board with the same cells, groups and constraints, and `constraint` added.
+    ///
+    /// The constraint is not applied until the board is solved, or
+    /// `discover_new_values` is called.
+    pub fn with_constraint(&self, constraint: impl Constraint + 'static) -> Self {
+        let mut constraints = self.constraints.to_vec();
+        constraints.push(Arc::new(constraint));
+        Board {
+            constraints: constraints.into(),
+            ..self.clone()
+        }
+    }
+
+    /// A board with the constraints of this board, followed by those of
+    /// `other`, so a board rebuilt from `other` keeps its chess rules and
+    /// custom constraints. The cages of `other` are not copied.
+    pub fn with_constraints_of(&self, other: &Board) -> Self {
+        let mut constraints = self.constraints.to_vec();
+        constraints.extend(other.constraints.iter().cloned());
+        Board {
+            constraints: constraints.into(),
+            ..self.clone()
+        }
+    }
+
+    /// All rules of this board: first the groups, then the cages, then the
+    /// other constraints.
+    pub fn constraints(&self) -> impl Iterator<Item = &dyn Constraint> {
+        self.groups.iter()
+            .map(|group| group as &dyn Constraint)
+            .chain(self.cages.iter().map(|cage| cage as &dyn Constraint))
+            .chain(self.constraints.iter().map(|constraint| constraint.as_ref()))
+    }
+
+    /// The groups of the layout, like rows, columns and blocks, without
+    /// those added for the cages.
+    pub fn layout_groups(&self) -> &[Group] {
+        &self.layout_groups
+    }
+
+    /// A board with the same groups, and all cells empty.
+    pub fn cleared(&self) -> Self {
+        Board {
+            cells: CellContainer::new(self.group_size()),
+            groups: Arc::clone(&self.groups),
+            layout_groups: Arc::clone(&self.layout_groups),
+            cages: Arc::clone(&self.cages),
+            constraints: Arc::clone(&self.constraints),
+            index: Arc::clone(&self.index),
         }
     }
 
@@ -23,39 +127,130 @@ impl Board {
         self.cells.get_cell(coord)
     }
 
-    fn group_size(&self) -> usize {
+    pub fn is_given(&self, coord: &Coord) -> bool {
+        self.get_cell(coord).is_given
+    }
+
+    /// The givens of the puzzle, filled in with `prefill_value`, with their values.
+    pub fn givens(&self) -> Vec<(Coord, usize)> {
+        self.cells.get_given_coords().into_iter()
+            .filter_map(|c| self.get_cell(&c).get_value().map(|v| (c, v)))
+            .collect()
+    }
+
+    /// The original puzzle: a board with the same groups, and only the givens filled in.
+    pub fn puzzle(&self) -> Board {
+        self.with_givens(self.givens().iter())
+            .expect("givens of a board do not conflict")
+    }
+
+    /// A board with the same groups, and `givens` filled in.
+    pub fn with_givens<'a>(&self, givens: impl Iterator<Item = &'a (Coord, usize)>) -> Result<Board> {
+        let mut puzzle = self.cleared();
+        for (coord, value) in givens {
+            puzzle.prefill_value(coord, *value)?;
+        }
+        Ok(puzzle)
+    }
+
+    pub fn group_size(&self) -> usize {
         self.cells.group_size()
     }
 
-    pub fn set_value(&mut self, coord: &Coord, value: usize) {
+    /// Set `value` at `coord`, and strike it through in all groups containing `coord`.
+    pub fn set_value(&mut self, coord: &Coord, value: usize) -> Result<()> {
+        self.check_placement(coord, value)?;
+        if let Some(peer) = self.find_peer_with_value(coord, value, |cell| cell.is_set) {
+            return Err(Error::Conflict { coord: peer, value });
+        }
         self.set_value_by(coord, value, |cell, value, set_value| {
             if set_value {
-                cell.set_value(value);
+                cell.set_value(value)
             } else {
-                cell.strike_through(value);
+                cell.strike_through(value)
             }
-        });
+        })
     }
 
-    pub fn prefill_value(&mut self, coord: &Coord, value: usize) {
+    /// Fill in a given `value` at `coord`, without propagating it to other cells.
+    pub fn prefill_value(&mut self, coord: &Coord, value: usize) -> Result<()> {
+        self.check_placement(coord, value)?;
+        if self.find_peer_with_value(coord, value, |_| true).is_some() {
+            return Err(Error::ConflictingGivens { coord: coord.clone(), value });
+        }
         self.set_value_by(coord, value, |cell, value, set_value| {
             if set_value {
-                cell.prefill_value(value);
+                cell.prefill_value(value)
+            } else {
+                Ok(())
             }
-        });
+        })
     }
 
-    fn set_value_by(&mut self, coord: &Coord, value: usize, setter: fn(&mut Cell, usize, bool)) {
-        let cells = &mut self.cells;
-        for cur in self.groups.iter()
-            .filter(|g| { g.contains_coord(coord) })
-            .flat_map(|g| &g.coordinates) {
-                let cell = cells.get_mut_cell(&cur);
-                setter(cell, value, cur == coord);
-            }
+    /// Remove `value` from the possible values at `coord`, without touching other cells.
+    pub fn strike_through(&mut self, coord: &Coord, value: usize) -> Result<()> {
+        self.check_placement(coord, value)?;
+        let cell = self.cells.get_mut_cell(coord);
+        cell.strike_through(value)?;
+        if cell.possibilities() == 0 {
+            return Err(Error::NoCandidates { coord: coord.clone() });
+        }
+        Ok(())
     }
 
-    fn discover_new_values(&mut self) -> Result<(), String> {
+    fn check_placement(&self, coord: &Coord, value: usize) -> Result<()> {
+        let group_size = self.group_size();
+        if coord.x >= group_size || coord.y >= group_size {
+            return Err(Error::CoordOutOfRange { coord: coord.clone(), group_size });
+        }
+        if value >= group_size {
+            return Err(Error::ValueOutOfRange { value, group_size });
+        }
+        Ok(())
+    }
+
+    /// All other cells sharing a group with `coord`.
+    pub fn peers_of(&self, coord: &Coord) -> impl ExactSizeIterator<Item = Coord> + '_ {
+        self.index.peers_of(coord)
+    }
+
+    /// Indices in `groups` of the groups containing `coord`.
+    pub fn groups_of(&self, coord: &Coord) -> &[usize] {
+        self.index.groups_of(coord)
+    }
+
+    fn find_peer_with_value(&self, coord: &Coord, value: usize, filter: fn(&Cell) -> bool) -> Option<Coord> {
+        let group_size = self.group_size();
+        self.index.peer_indices_of(coord).iter()
+            .map(|index| *index as usize)
+            .find(|index| {
+                let cell = self.cells.get_cell_at(*index);
+                filter(cell) && cell.get_value() == Some(value)
+            })
+            .map(|index| Coord::new(index % group_size, index / group_size))
+    }
+
+    fn set_value_by(&mut self, coord: &Coord, value: usize, setter: fn(&mut Cell, usize, bool) -> Result<()>) -> Result<()> {
+        setter(self.cells.get_mut_cell(coord), value, true)?;
+        for cur in self.index.peer_indices_of(coord) {
+            setter(self.cells.get_mut_cell_at(*cur as usize), value, false)?;
+        }
+        Ok(())
+    }
+
+    /// Set all values that follow directly from the current possible values.
+    ///
+    /// Repeatedly sets naked singles, cells with only one possible value,
+    /// and hidden singles, values with only one possible cell in a group.
+    /// When neither is found, the constraints remove the values they exclude.
+    pub fn discover_new_values(&mut self) -> Result<()> {
+        while self.discover_naked_singles()?
+            || self.discover_hidden_singles()?
+            || self.prune_constraints()? {}
+        Ok(())
+    }
+
+    fn discover_naked_singles(&mut self) -> Result<bool> {
         let coords_to_update: Vec<Coord>;
         {
             coords_to_update = self.cells.get_cell_coords_to_update();
@@ -67,15 +262,65 @@ impl Board {
                 cell = self.get_cell(&coord);
             }
             match cell.get_value() {
-                Some(v) => self.set_value(&coord, v),
-                None => return Err(String::from("Value conflict detected, no solution for this puzzle")),
+                Some(v) => self.set_value(&coord, v)?,
+                None => return Err(Error::NoCandidates { coord }),
             };
         }
-        if discovered_new_values {
-            self.discover_new_values()
-        } else {
-            Ok(())
+        Ok(discovered_new_values)
+    }
+
+    /// Set values that can only go in one cell of a group.
+    ///
+    /// Only groups with a cell for every value are considered.
+    fn discover_hidden_singles(&mut self) -> Result<bool> {
+        let group_size = self.group_size();
+        let groups = Arc::clone(&self.groups);
+        let mut discovered_new_values = false;
+        let mut places = vec![0; group_size];
+        let mut last_place = vec![0; group_size];
+        for (group_index, group) in groups.iter().enumerate() {
+            if group.coordinates.len() != group_size {
+                continue;
+            }
+            places.iter_mut().for_each(|p| *p = 0);
+            for (index, coord) in group.coordinates.iter().enumerate() {
+                for value in self.get_cell(coord).possible_values.iter() {
+                    places[value] += 1;
+                    last_place[value] = index;
+                }
+            }
+            for value in 0..group_size {
+                match places[value] {
+                    0 => return Err(Error::NoPlaceForValue { group: group_index, value }),
+                    1 => {
+                        let coord = &group.coordinates[last_place[value]];
+                        let cell = self.get_cell(coord);
+                        if !cell.is_set && cell.is_possible(value) {
+                            self.set_value(coord, value)?;
+                            discovered_new_values = true;
+                        }
+                    },
+                    _ => (),
+                }
+            }
+        }
+        Ok(discovered_new_values)
+    }
+
+    /// Let the cages and every other constraint remove the values they
+    /// exclude. The groups need no pruning, as placing a value already
+    /// strikes it from all peers.
+    fn prune_constraints(&mut self) -> Result<bool> {
+        let cages = Arc::clone(&self.cages);
+        let constraints = Arc::clone(&self.constraints);
+        let mut pruned = false;
+        for cage in cages.iter() {
+            pruned |= cage.prune(self)?;
+        }
+        for constraint in constraints.iter() {
+            pruned |= constraint.prune(self)?;
         }
+        Ok(pruned)
     }
 
     pub fn is_solved(&self) -> bool {
@@ -88,19 +333,34 @@ impl Board {
         is_solved
     }
 
-    fn is_valid_group(&self, group: &Group) -> bool {
-        let mut validation_cell = Cell::new(self.group_size());
-        for coord in &group.coordinates {
-            if let Some(value) = self.get_cell(&coord).get_value() {
-                validation_cell.strike_through(value);
-            }
+    /// Whether all cells are filled in, following every constraint.
+    pub fn is_valid_solution(&self) -> bool {
+        self.constraints()
+            .all(|constraint| constraint.is_satisfied(self))
+    }
+
+    /// Iterate over the solutions of this board.
+    ///
+    /// Solutions are searched one at a time, only when the next one is
+    /// requested. So `solutions().take(2)` stops searching as soon as a
+    /// second solution is found.
+    pub fn solutions(&self) -> Solutions {
+        Solutions {
+            stack: vec![self.clone()],
+            random: None,
         }
-        validation_cell.possibilities() == 0
     }
 
-    pub fn is_valid_solution(&self) -> bool {
-        self.groups.iter()
-            .all(|group| self.is_valid_group(group))
+    /// Iterate over the solutions of this board, trying the possible values
+    /// of each pivot cell in a random order.
+    ///
+    /// The order only depends on `seed`, so the same seed gives the same
+    /// solutions in the same order.
+    pub fn shuffled_solutions(&self, seed: u64) -> Solutions {
+        Solutions {
+            stack: vec![self.clone()],
+            random: Some(Random::new(seed)),
+        }
     }
 
     pub fn solve(&self) -> Vec<Self> {
@@ -115,11 +375,12 @@ impl Board {
                         Some(p) => {
                             let pivot_cell = puzzle.get_cell(&p);
                             (0..self.group_size()).into_par_iter().flat_map(|i| {
-                                let i = i as usize;
-                                if pivot_cell.possible_values[i] {
+                                if pivot_cell.is_possible(i) {
                                     let mut subpuzzle = puzzle.clone();
-                                    subpuzzle.set_value(&p, i);
-                                    subpuzzle.solve()
+                                    match subpuzzle.set_value(&p, i) {
+                                        Ok(()) => subpuzzle.solve(),
+                                        Err(_) => Vec::new(),
+                                    }
                                 } else {
                                     Vec::new()
                                 }
@@ -139,6 +400,12 @@ impl Board {
         }
     }
 
+    /// Find at most `limit` solutions of this board. Unlike `solve`, the
+    /// search stops as soon as `limit` solutions are found.
+    pub fn solve_up_to(&self, limit: usize) -> Vec<Self> {
+        self.solutions().take(limit).collect()
+    }
+
     pub fn count_solutions(self) -> usize {
         let mut puzzle = self;
         match puzzle.discover_new_values() {
@@ -151,11 +418,12 @@ impl Board {
                         Some(p) => {
                             let pivot_cell = puzzle.get_cell(&p);
                             (0..puzzle.group_size()).into_par_iter().map(|i| {
-                                let i = i as usize;
-                                if pivot_cell.possible_values[i] {
+                                if pivot_cell.is_possible(i) {
                                     let mut subpuzzle = puzzle.clone();
-                                    subpuzzle.set_value(&p, i);
-                                    subpuzzle.count_solutions()
+                                    match subpuzzle.set_value(&p, i) {
+                                        Ok(()) => subpuzzle.count_solutions(),
+                                        Err(_) => 0,
+                                    }
                                 } else {
                                     0
                                 }
@@ -175,6 +443,74 @@ impl Board {
         }
     }
 
+    /// Count the solutions of this board, but stop searching as soon as
+    /// `limit` solutions are found.
+    ///
+    /// All parallel branches of the search share the count, so they all
+    /// stop once the limit is reached.
+    pub fn count_solutions_up_to(&self, limit: usize) -> usize {
+        let found = AtomicUsize::new(0);
+        self.clone().count_solutions_into(limit, &found);
+        found.load(Ordering::SeqCst).min(limit)
+    }
+
+    fn count_solutions_into(self, limit: usize, found: &AtomicUsize) {
+        if found.load(Ordering::Relaxed) >= limit {
+            return;
+        }
+        let mut puzzle = self;
+        if puzzle.discover_new_values().is_err() {
+            return;
+        }
+        if puzzle.is_solved() {
+            found.fetch_add(1, Ordering::SeqCst);
+            return;
+        }
+        if let Some(p) = puzzle.find_pivot_coord() {
+            let pivot_cell = puzzle.get_cell(&p);
+            (0..puzzle.group_size()).into_par_iter().for_each(|i| {
+                if pivot_cell.is_possible(i) && found.load(Ordering::Relaxed) < limit {
+                    let mut subpuzzle = puzzle.clone();
+                    if subpuzzle.set_value(&p, i).is_ok() {
+                        subpuzzle.count_solutions_into(limit, found);
+                    }
+                }
+            });
+        }
+    }
+
+    /// Search for solutions the way `solutions()` does, recording how much
+    /// guessing was needed, until `limit` solutions are found or the search
+    /// is exhausted.
+    pub fn search_statistics(&self, limit: usize) -> SearchStatistics {
+        let mut statistics = SearchStatistics::default();
+        self.clone().search_into(0, limit, &mut statistics);
+        statistics
+    }
+
+    fn search_into(self, depth: usize, limit: usize, statistics: &mut SearchStatistics) {
+        let mut puzzle = self;
+        if statistics.solutions >= limit || puzzle.discover_new_values().is_err() {
+            return;
+        }
+        statistics.depth = statistics.depth.max(depth);
+        if puzzle.is_solved() {
+            statistics.solutions += 1;
+            return;
+        }
+        if let Some(p) = puzzle.find_pivot_coord() {
+            for i in 0..puzzle.group_size() {
+                if puzzle.get_cell(&p).is_possible(i) && statistics.solutions < limit {
+                    statistics.branches += 1;
+                    let mut subpuzzle = puzzle.clone();
+                    if subpuzzle.set_value(&p, i).is_ok() {
+                        subpuzzle.search_into(depth + 1, limit, statistics);
+                    }
+                }
+            }
+        }
+    }
+
     fn find_pivot_coord(&self) -> Option<Coord> {
         let mut open_cells: Vec<(Coord, usize)> = Vec::new();
         for y in 0..self.group_size() {
@@ -201,11 +537,51 @@ impl Board {
                 None => result = Some(c),
             }
         }
-        match result {
-            Some((coord, _)) => {
-                Some(coord)
-            },
-            None => None
+        result.map(|(coord, _)| coord)
+    }
+}
+
+/// How much guessing a depth-first search over the solutions of a board needed.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct SearchStatistics {
+    /// The number of values tried at pivot cells.
+    pub branches: usize,
+    /// The largest number of nested guesses.
+    pub depth: usize,
+    pub solutions: usize,
+}
+
+/// Lazy depth-first search over the solutions of a board.
+pub struct Solutions {
+    stack: Vec<Board>,
+    random: Option<Random>,
+}
+
+impl Iterator for Solutions {
+    type Item = Board;
+
+    fn next(&mut self) -> Option<Board> {
+        while let Some(mut puzzle) = self.stack.pop() {
+            if puzzle.discover_new_values().is_err() {
+                continue;
+            }
+            if puzzle.is_solved() {
+                return Some(puzzle);
+            }
+            if let Some(p) = puzzle.find_pivot_coord() {
+                let mut values: Vec<usize> = puzzle.get_cell(&p).possible_values.iter().collect();
+                if let Some(random) = &mut self.random {
+                    random.shuffle(&mut values);
+                }
+                // Push in reverse, so the first values are tried first.
+                for i in values.into_iter().rev() {
+                    let mut subpuzzle = puzzle.clone();
+                    if subpuzzle.set_value(&p, i).is_ok() {
+                        self.stack.push(subpuzzle);
+                    }
+                }
+            }
         }
+        None
     }
 }
@@ -0,0 +1,182 @@
+//! Killer cages: cells whose values add up to a given sum, without repeats.
+//!
+//! Sums count the values from 1, as they are written in the text formats,
+//! while the values of cells are 0-based.
+
+use std::collections::HashMap;
+
+use super::board::Board;
+use super::candidates::Candidates;
+use super::cell_container::CellContainer;
+use super::constraint::Constraint;
+use super::coord::Coord;
+use super::error::{Error, Result};
+use super::explain::cell_name;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Cage {
+    pub coordinates: Vec<Coord>,
+    pub sum: usize,
+}
+
+impl Cage {
+    /// A cage of different cells adding up to `sum`. Whether the cage fits
+    /// a board is checked when it is added, see `Board::with_cages`.
+    pub fn new(coordinates: Vec<Coord>, sum: usize) -> Result<Self> {
+        if coordinates.is_empty() {
+            return Err(Error::Geometry {
+                message: String::from("a cage has no cells"),
+            });
+        }
+        for (index, coord) in coordinates.iter().enumerate() {
+            if coordinates[..index].contains(coord) {
+                return Err(Error::Geometry {
+                    message: format!("{} is in the same cage twice", cell_name(coord)),
+                });
+            }
+        }
+        Ok(Cage {
+            coordinates,
+            sum,
+        })
+    }
+
+    /// The top left cell, which names the cage and shows its sum when printed.
+    pub fn top_left(&self) -> &Coord {
+        self.coordinates.iter()
+            .min_by_key(|c| (c.y, c.x))
+            .expect("cages are not empty")
+    }
+
+    /// Check that the cage fits a board of `group_size`: it has cells, all
+    /// on the board, not more than there are values, and its sum can be
+    /// reached with different values.
+    pub fn check(&self, group_size: usize) -> Result<()> {
+        if self.coordinates.is_empty() {
+            return Err(Error::Geometry {
+                message: String::from("a cage has no cells"),
+            });
+        }
+        if let Some(coord) = self.coordinates.iter().find(|c| c.x >= group_size || c.y >= group_size) {
+            return Err(Error::CoordOutOfRange { coord: coord.clone(), group_size });
+        }
+        let name = cell_name(self.top_left());
+        if self.coordinates.len() > group_size {
+            return Err(Error::Geometry {
+                message: format!("cage at {} has {} cells, at most {} fit",
+                                 name, self.coordinates.len(), group_size),
+            });
+        }
+        let (min_sum, max_sum) = self.sum_range(group_size);
+        if !(min_sum..=max_sum).contains(&self.sum) {
+            return Err(Error::Geometry {
+                message: format!("cage at {} cannot add up to {}, its sum lies between {} and {}",
+                                 name, self.sum, min_sum, max_sum),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn contains_coord(&self, coord: &Coord) -> bool {
+        self.coordinates.contains(coord)
+    }
+
+    /// The smallest and largest sum of a cage of this size on a board of `group_size`.
+    pub fn sum_range(&self, group_size: usize) -> (usize, usize) {
+        let len = self.coordinates.len();
+        (len * (len + 1) / 2, len * (2 * group_size + 1 - len) / 2)
+    }
+
+    /// For every cell of the cage, the possible values that take part in
+    /// at least one combination of different values adding up to the sum.
+    ///
+    /// A cell without any such value means the cage cannot be completed.
+    pub fn supported_values(&self, cells: &CellContainer) -> Vec<Candidates> {
+        let group_size = cells.group_size();
+        let mut search = SumSearch {
+            candidates: self.coordinates.iter().map(|c| &cells.get_cell(c).possible_values).collect(),
+            supported: vec![Candidates::empty(group_size); self.coordinates.len()],
+            group_size,
+            feasible: HashMap::new(),
+        };
+        // Every cell contributes one more than its 0-based value.
+        if let Some(remaining) = self.sum.checked_sub(self.coordinates.len()) {
+            search.search(0, &mut Candidates::empty(group_size), remaining);
+        }
+        search.supported
+    }
+}
+
+/// The values in a cage add up to its sum. That they are different is
+/// left to the group of the cage.
+impl Constraint for Cage {
+    fn prune(&self, board: &mut Board) -> Result<bool> {
+        let supported = self.supported_values(&board.cells);
+        let mut pruned = false;
+        for (coord, values) in self.coordinates.iter().zip(supported) {
+            if values.is_empty() {
+                return Err(Error::NoCandidates { coord: coord.clone() });
+            }
+            let excluded: Vec<usize> = board.get_cell(coord).possible_values.iter()
+                .filter(|v| !values.contains(*v))
+                .collect();
+            for value in excluded {
+                board.strike_through(coord, value)?;
+                pruned = true;
+            }
+        }
+        Ok(pruned)
+    }
+
+    fn is_satisfied(&self, board: &Board) -> bool {
+        let total: Option<usize> = self.coordinates.iter()
+            .map(|c| board.get_cell(c).get_value().map(|v| v + 1))
+            .sum();
+        total == Some(self.sum)
+    }
+}
+
+/// Depth-first search over the values of the cells of a cage, in order.
+struct SumSearch<'a> {
+    candidates: Vec<&'a Candidates>,
+    supported: Vec<Candidates>,
+    group_size: usize,
+    /// Whether the cells after the used values can be completed. The used
+    /// values also tell the next cell and the remaining sum, so searching
+    /// them again would mark the same values.
+    feasible: HashMap<Candidates, bool>,
+}
+
+impl SumSearch<'_> {
+    /// Whether the cells from `index` on can add up to `remaining`, with
+    /// values not in `used`. Marks the values that do as supported.
+    fn search(&mut self, index: usize, used: &mut Candidates, remaining: usize) -> bool {
+        let left = self.candidates.len() - index;
+        if left == 0 {
+            return remaining == 0;
+        }
+        // Different values from 0 up, or down from the highest value.
+        let (min_sum, max_sum) = (left * (left - 1) / 2, left * (2 * self.group_size - left - 1) / 2);
+        if remaining < min_sum || remaining > max_sum {
+            return false;
+        }
+        if let Some(feasible) = self.feasible.get(used) {
+            return *feasible;
+        }
+        let mut feasible = false;
+        let values: Vec<usize> = self.candidates[index].iter()
+            .take_while(|v| *v <= remaining)
+            .filter(|v| !used.contains(*v))
+            .collect();
+        for value in values {
+            used.insert(value);
+            if self.search(index + 1, used, remaining - value) {
+                self.supported[index].insert(value);
+                feasible = true;
+            }
+            used.remove(value);
+        }
+        self.feasible.insert(used.clone(), feasible);
+        feasible
+    }
+}
@@ -0,0 +1,115 @@
+/// A set of values `0..size`, stored as a bitmask.
+///
+/// Sets for up to 64 values fit in a single word, so cloning them does not
+/// allocate. Larger sets fall back to a boxed slice of words.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Candidates {
+    Small(u64),
+    Wide(Box<[u64]>),
+}
+
+const WORD_BITS: usize = 64;
+
+impl Candidates {
+    /// The set of all values `0..size`.
+    pub fn full(size: usize) -> Self {
+        let mut candidates = Candidates::empty(size);
+        for value in 0..size {
+            candidates.insert(value);
+        }
+        candidates
+    }
+
+    /// The empty set, able to hold values `0..size`.
+    pub fn empty(size: usize) -> Self {
+        if size <= WORD_BITS {
+            Candidates::Small(0)
+        } else {
+            Candidates::Wide(vec![0; size.div_ceil(WORD_BITS)].into_boxed_slice())
+        }
+    }
+
+    fn words(&self) -> &[u64] {
+        match self {
+            Candidates::Small(word) => std::slice::from_ref(word),
+            Candidates::Wide(words) => words,
+        }
+    }
+
+    fn words_mut(&mut self) -> &mut [u64] {
+        match self {
+            Candidates::Small(word) => std::slice::from_mut(word),
+            Candidates::Wide(words) => words,
+        }
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        self.words().get(value / WORD_BITS)
+            .is_some_and(|word| word & (1 << (value % WORD_BITS)) != 0)
+    }
+
+    pub fn insert(&mut self, value: usize) {
+        self.words_mut()[value / WORD_BITS] |= 1 << (value % WORD_BITS);
+    }
+
+    pub fn remove(&mut self, value: usize) {
+        self.words_mut()[value / WORD_BITS] &= !(1 << (value % WORD_BITS));
+    }
+
+    /// Remove all values, except `value`.
+    pub fn retain_only(&mut self, value: usize) {
+        for word in self.words_mut() {
+            *word = 0;
+        }
+        self.insert(value);
+    }
+
+    /// Add all values of `other` to this set.
+    pub fn union_with(&mut self, other: &Candidates) {
+        for (word, other_word) in self.words_mut().iter_mut().zip(other.words()) {
+            *word |= other_word;
+        }
+    }
+
+    /// The number of values in the set.
+    pub fn len(&self) -> usize {
+        self.words().iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words().iter().all(|word| *word == 0)
+    }
+
+    /// The lowest value in the set.
+    pub fn first(&self) -> Option<usize> {
+        self.words().iter().enumerate()
+            .find(|(_, word)| **word != 0)
+            .map(|(index, word)| index * WORD_BITS + word.trailing_zeros() as usize)
+    }
+
+    /// The value in the set, when it contains exactly one value.
+    pub fn single(&self) -> Option<usize> {
+        if self.len() == 1 {
+            self.first()
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over the values in the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words().iter().enumerate()
+            .flat_map(|(index, word)| {
+                let mut word = *word;
+                std::iter::from_fn(move || {
+                    if word == 0 {
+                        None
+                    } else {
+                        let bit = word.trailing_zeros() as usize;
+                        word &= word - 1;
+                        Some(index * WORD_BITS + bit)
+                    }
+                })
+            })
+    }
+}
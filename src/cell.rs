@@ -1,61 +1,68 @@
+use super::candidates::Candidates;
+use super::error::{Error, Result};
+
 /// Captures the possible values of a single Cell.
 #[derive(Clone, Debug)]
 pub struct Cell {
-    pub possible_values: Box<[bool]>,
+    pub possible_values: Candidates,
     pub is_set: bool,
+    /// Whether the value was given by the puzzle, rather than found while solving.
+    pub is_given: bool,
+    size: usize,
 }
 
 impl Cell {
     pub fn new(size: usize) -> Self {
         Cell {
-            possible_values: vec![true; size].into_boxed_slice(),
+            possible_values: Candidates::full(size),
             is_set: false,
+            is_given: false,
+            size,
         }
     }
 
-    pub fn strike_through(&mut self, index: usize) {
+    pub fn strike_through(&mut self, index: usize) -> Result<()> {
         if self.is_set && self.get_value() == Some(index) {
-            panic!("Cannot strikethrough a set value.");
+            return Err(Error::ValueAlreadySet { value: index });
         }
-        self.possible_values[index] = false;
+        self.possible_values.remove(index);
+        Ok(())
     }
 
     fn len(&self) -> usize {
-        self.possible_values.len()
+        self.size
+    }
+
+    pub fn is_possible(&self, value: usize) -> bool {
+        self.possible_values.contains(value)
     }
 
     pub fn get_value(&self) -> Option<usize> {
-        let possible_values: Vec<usize> = self.possible_values.iter().enumerate()
-            .filter_map(|(index, is_possible)| if *is_possible {
-                Some(index)
-            } else {
-                None
-            })
-            .collect();
-        if possible_values.len() == 1 {
-            Some(possible_values[0])
-        } else {
-            None
-        }
+        self.possible_values.single()
     }
 
-    pub fn set_value(&mut self, value: usize) {
-        self.prefill_value(value);
-        self.is_set = true
+    pub fn set_value(&mut self, value: usize) -> Result<()> {
+        self.retain_value(value)?;
+        self.is_set = true;
+        Ok(())
     }
 
-    pub fn prefill_value(&mut self, value: usize) {
+    /// Fill in `value` as a given of the puzzle.
+    pub fn prefill_value(&mut self, value: usize) -> Result<()> {
+        self.retain_value(value)?;
+        self.is_given = true;
+        Ok(())
+    }
+
+    fn retain_value(&mut self, value: usize) -> Result<()> {
         if value >= self.len() {
-            panic!("Illegal value {}, must be smaller than {}", value, self.len());
-        }
-        for i in 0..self.len() {
-            self.possible_values[i] = i == value
+            return Err(Error::ValueOutOfRange { value, group_size: self.len() });
         }
+        self.possible_values.retain_only(value);
+        Ok(())
     }
 
     pub fn possibilities(&self) -> usize {
-        self.possible_values.iter()
-            .filter(|is_possible| **is_possible)
-            .count()
+        self.possible_values.len()
     }
 }
@@ -35,6 +35,15 @@ impl CellContainer {
         &mut self.cells[self.index_of(coord)]
     }
 
+    /// The cell at `index`, counting row by row, as in `PeerIndex`.
+    pub fn get_cell_at(&self, index: usize) -> &Cell {
+        &self.cells[index]
+    }
+
+    pub fn get_mut_cell_at(&mut self, index: usize) -> &mut Cell {
+        &mut self.cells[index]
+    }
+
     pub fn get_cell_coords_to_update(&self) -> Vec<Coord> {
         let mut cell_coords_to_update = Vec::new();
         let group_size = self.group_size();
@@ -49,4 +58,19 @@ impl CellContainer {
         }
         cell_coords_to_update
     }
+
+    /// The coordinates of the cells holding a given of the puzzle.
+    pub fn get_given_coords(&self) -> Vec<Coord> {
+        let mut given_coords = Vec::new();
+        let group_size = self.group_size();
+        for y in 0..group_size {
+            for x in 0..group_size {
+                let coord = Coord::new(x, y);
+                if self.get_cell(&coord).is_given {
+                    given_coords.push(coord);
+                }
+            }
+        }
+        given_coords
+    }
 }
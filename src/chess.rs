@@ -0,0 +1,101 @@
+//! Chess rules, where equal values may not be a chess move apart.
+//!
+//! The anti-king rule forbids equal values in any two cells a king's move
+//! apart, orthogonally or diagonally. Most of these pairs already share a
+//! row, column or block, so in effect it adds the diagonal neighbours in
+//! other blocks.
+
+use super::board::Board;
+use super::constraint::Constraint;
+use super::coord::Coord;
+use super::error::{Error, Result};
+
+/// A rule forbidding equal values in cells that attack each other.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChessRule {
+    /// Equal values may not be a knight's move apart.
+    AntiKnight,
+    /// Equal values may not touch, not even diagonally.
+    AntiKing,
+}
+
+const KNIGHT_MOVES: [(isize, isize); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const KING_MOVES: [(isize, isize); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+
+impl ChessRule {
+    pub const ALL: [ChessRule; 2] = [ChessRule::AntiKnight, ChessRule::AntiKing];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChessRule::AntiKnight => "anti-knight",
+            ChessRule::AntiKing => "anti-king",
+        }
+    }
+
+    /// Find a rule by name, ignoring case and punctuation, so `AntiKnight`
+    /// and `anti-knight` both work.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalize = |s: &str| s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let name = normalize(name);
+        ChessRule::ALL.iter()
+            .find(|rule| normalize(rule.name()) == name)
+            .copied()
+    }
+
+    /// The cells attacked from `coord` on a board of `group_size`.
+    pub fn attacked(&self, coord: &Coord, group_size: usize) -> Vec<Coord> {
+        let moves = match self {
+            ChessRule::AntiKnight => &KNIGHT_MOVES,
+            ChessRule::AntiKing => &KING_MOVES,
+        };
+        moves.iter()
+            .filter_map(|(dx, dy)| {
+                let x = coord.x.checked_add_signed(*dx).filter(|x| *x < group_size)?;
+                let y = coord.y.checked_add_signed(*dy).filter(|y| *y < group_size)?;
+                Some(Coord::new(x, y))
+            })
+            .collect()
+    }
+}
+
+/// Placing a value strikes it from the attacked cells.
+impl Constraint for ChessRule {
+    fn prune(&self, board: &mut Board) -> Result<bool> {
+        let group_size = board.group_size();
+        let mut pruned = false;
+        for y in 0..group_size {
+            for x in 0..group_size {
+                let coord = Coord::new(x, y);
+                let value = match board.get_cell(&coord).get_value() {
+                    Some(value) => value,
+                    None => continue,
+                };
+                for other in self.attacked(&coord, group_size) {
+                    let cell = board.get_cell(&other);
+                    if cell.get_value() == Some(value) {
+                        return Err(Error::Conflict { coord: other, value });
+                    }
+                    if cell.is_possible(value) {
+                        board.strike_through(&other, value)?;
+                        pruned = true;
+                    }
+                }
+            }
+        }
+        Ok(pruned)
+    }
+
+    fn is_satisfied(&self, board: &Board) -> bool {
+        let group_size = board.group_size();
+        (0..group_size)
+            .flat_map(|y| (0..group_size).map(move |x| Coord::new(x, y)))
+            .all(|coord| {
+                let value = board.get_cell(&coord).get_value();
+                value.is_some() && self.attacked(&coord, group_size).iter()
+                    .all(|other| board.get_cell(other).get_value() != value)
+            })
+    }
+}
@@ -0,0 +1,24 @@
+//! Rules the values of a board must follow.
+//!
+//! Groups, where all values are different, are the rule every board has.
+//! Other rules, like the sums of killer cages, implement `Constraint` as
+//! well and are added to a board with `Board::with_constraint`.
+
+use super::board::Board;
+use super::error::Result;
+
+/// A rule on the values of a board.
+///
+/// Constraints are shared between the boards of a search, which may run
+/// in parallel, so they must be `Send` and `Sync`.
+pub trait Constraint: Send + Sync {
+    /// Remove the possible values this rule excludes, returning whether
+    /// any value was removed.
+    ///
+    /// Fails when the rule can no longer be satisfied, for example when a
+    /// cell has no possible values left.
+    fn prune(&self, board: &mut Board) -> Result<bool>;
+
+    /// Whether the values of a completely filled in board follow this rule.
+    fn is_satisfied(&self, board: &Board) -> bool;
+}
@@ -0,0 +1,137 @@
+//! Generating puzzles of a requested difficulty.
+//!
+//! Puzzles are generated one after the other, and graded, until enough of
+//! them match the target or the time budget runs out. Like all generated
+//! puzzles, they have a unique solution.
+
+use std::time::{Duration, Instant};
+
+use super::generate::Symmetry;
+use super::grade::Grade;
+use super::logic::Technique;
+use super::random::Random;
+use super::RectangularBoard;
+
+/// The difficulty a generated puzzle must have.
+#[derive(Clone, Debug)]
+pub struct DifficultyTarget {
+    /// The lowest rating, see `Grade::rating`.
+    pub min_rating: f32,
+    /// The highest rating.
+    pub max_rating: f32,
+    /// The fewest guesses needed by the search, see `SearchStatistics::branches`.
+    pub min_branches: usize,
+    /// The most guesses needed by the search.
+    pub max_branches: usize,
+    /// A technique the logical solver must use.
+    pub technique: Option<Technique>,
+    /// The symmetry of the givens.
+    pub symmetry: Symmetry,
+}
+
+impl Default for DifficultyTarget {
+    fn default() -> Self {
+        DifficultyTarget {
+            min_rating: 0.0,
+            max_rating: f32::MAX,
+            min_branches: 0,
+            max_branches: usize::MAX,
+            technique: None,
+            symmetry: Symmetry::None,
+        }
+    }
+}
+
+impl DifficultyTarget {
+    /// Puzzles with a rating from `min_rating` up to and including `max_rating`.
+    pub fn rating(min_rating: f32, max_rating: f32) -> Self {
+        DifficultyTarget {
+            min_rating,
+            max_rating,
+            ..DifficultyTarget::default()
+        }
+    }
+
+    /// Puzzles the logical solver needs `technique` for.
+    pub fn requiring(technique: Technique) -> Self {
+        DifficultyTarget {
+            technique: Some(technique),
+            ..DifficultyTarget::default()
+        }
+    }
+
+    /// A named band of ratings:
+    ///
+    /// * `easy`: singles only.
+    /// * `medium`: up to intersections, pairs and X-Wings.
+    /// * `hard`: up to triples, quads, Swordfish, wings and colouring.
+    /// * `expert`: puzzles that need guessing.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (min_rating, max_rating) = match name.to_ascii_lowercase().as_str() {
+            "easy" => (0.0, 2.3),
+            "medium" => (2.6, 3.4),
+            "hard" => (3.6, 6.5),
+            "expert" => (7.0, 11.9),
+            _ => return None,
+        };
+        Some(DifficultyTarget::rating(min_rating, max_rating))
+    }
+
+    pub fn matches(&self, grade: &Grade) -> bool {
+        (self.min_rating..=self.max_rating).contains(&grade.rating)
+            && (self.min_branches..=self.max_branches).contains(&grade.search.branches)
+            && self.technique.is_none_or(|t| grade.techniques.contains(&t))
+    }
+}
+
+/// How far the generation of puzzles has come.
+#[derive(Clone, Debug)]
+pub struct Progress {
+    /// The number of puzzles generated so far.
+    pub attempts: usize,
+    /// The number of those puzzles that match the target.
+    pub found: usize,
+    pub elapsed: Duration,
+}
+
+impl RectangularBoard {
+    /// Generate up to `count` puzzles matching `target`, with the geometry
+    /// and variant of this board, see `generate`.
+    ///
+    /// Stops early when `budget` is used up, so fewer puzzles may be
+    /// returned. The budget is checked between puzzles, so it can be
+    /// exceeded by the time needed for a single puzzle. `progress` is
+    /// called after every generated puzzle. The same `seed` always gives
+    /// the same puzzles, in the same order.
+    pub fn generate_with_difficulty(
+        &self,
+        seed: u64,
+        target: &DifficultyTarget,
+        count: usize,
+        budget: Duration,
+        mut progress: impl FnMut(&Progress),
+    ) -> Vec<(RectangularBoard, Grade)> {
+        let start = Instant::now();
+        let mut random = Random::new(seed);
+        let mut puzzles = Vec::new();
+        let mut attempts = 0;
+        while puzzles.len() < count && start.elapsed() < budget {
+            let puzzle = match self.generate_symmetric(random.next_u64(), target.symmetry) {
+                Some(puzzle) => puzzle,
+                None => break,
+            };
+            attempts += 1;
+            if let Ok(grade) = puzzle.grade() {
+                if target.matches(&grade) {
+                    puzzles.push((puzzle, grade));
+                }
+            }
+            progress(&Progress {
+                attempts,
+                found: puzzles.len(),
+                elapsed: start.elapsed(),
+            });
+        }
+        puzzles
+    }
+}
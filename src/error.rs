@@ -0,0 +1,83 @@
+use std::fmt;
+
+use super::coord::Coord;
+
+/// Errors reported while reading or manipulating a puzzle.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// The input could not be parsed. `line` and `column` are 1-based.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A value does not fit on the board, values range from 0 to `group_size` - 1.
+    ValueOutOfRange {
+        value: usize,
+        group_size: usize,
+    },
+    /// A coordinate lies outside the board.
+    CoordOutOfRange {
+        coord: Coord,
+        group_size: usize,
+    },
+    /// The requested board geometry is not supported.
+    Geometry {
+        message: String,
+    },
+    /// Two givens with the same value share a group.
+    ConflictingGivens {
+        coord: Coord,
+        value: usize,
+    },
+    /// Placing `value` contradicts a value already set at `coord`.
+    Conflict {
+        coord: Coord,
+        value: usize,
+    },
+    /// The cell at `coord` has no possible values left.
+    NoCandidates {
+        coord: Coord,
+    },
+    /// `value` cannot be placed anywhere in the group at index `group`.
+    NoPlaceForValue {
+        group: usize,
+        value: usize,
+    },
+    /// A set value of a cell cannot be struck through.
+    ValueAlreadySet {
+        value: usize,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse { line, column, message } =>
+                write!(f, "parse error at line {}, column {}: {}", line, column, message),
+            Error::ValueOutOfRange { value, group_size } =>
+                write!(f, "illegal value {}, must be smaller than {}", value, group_size),
+            Error::CoordOutOfRange { coord, group_size } =>
+                write!(f, "coordinate ({}, {}) lies outside the {}x{} board",
+                       coord.x, coord.y, group_size, group_size),
+            Error::Geometry { message } =>
+                write!(f, "unsupported geometry: {}", message),
+            Error::ConflictingGivens { coord, value } =>
+                write!(f, "given {} at ({}, {}) conflicts with another given",
+                       value, coord.x, coord.y),
+            Error::Conflict { coord, value } =>
+                write!(f, "value {} conflicts with the value set at ({}, {})",
+                       value, coord.x, coord.y),
+            Error::NoCandidates { coord } =>
+                write!(f, "no possible values left at ({}, {})", coord.x, coord.y),
+            Error::NoPlaceForValue { group, value } =>
+                write!(f, "value {} has no place left in group {}", value, group),
+            Error::ValueAlreadySet { value } =>
+                write!(f, "cannot strike through set value {}", value),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,151 @@
+//! Rendering the steps of a logical solution as text or JSON.
+//!
+//! Only the logical solver records steps, see `Board::solve_logically`.
+//! Values found by `Board::discover_new_values` or the search, and values
+//! removed by cages and other constraints, are not part of the log.
+//!
+//! Cells are written as `r<row>c<column>` and values as in the text
+//! formats, both 1-based. JSON output is written by hand, to keep the
+//! crate free of serialization dependencies.
+
+use super::board::Board;
+use super::coord::Coord;
+use super::format::value_to_symbol;
+use super::group::Group;
+use super::logic::{LogicalSolution, Step};
+
+pub fn cell_name(coord: &Coord) -> String {
+    format!("r{}c{}", coord.y + 1, coord.x + 1)
+}
+
+fn cell_list(coords: &[Coord]) -> String {
+    coords.iter().map(cell_name).collect::<Vec<_>>().join(", ")
+}
+
+fn top_left(group: &Group) -> &Coord {
+    group.coordinates.iter()
+        .min_by_key(|c| (c.y, c.x))
+        .expect("groups are not empty")
+}
+
+impl Board {
+    /// A name for the group at `index` in `groups`, like `row 3` or `block at r4c7`.
+    ///
+    /// Cages, and groups that are not a row, a column or a diagonal, are
+    /// named after their top left cell.
+    pub fn group_name(&self, index: usize) -> String {
+        let group = &self.groups[index];
+        let first = &group.coordinates[0];
+        if self.cages.iter().any(|cage| cage.coordinates == group.coordinates) {
+            format!("cage at {}", cell_name(top_left(group)))
+        } else if group.coordinates.len() > 1 && group.coordinates.iter().all(|c| c.y == first.y) {
+            format!("row {}", first.y + 1)
+        } else if group.coordinates.len() > 1 && group.coordinates.iter().all(|c| c.x == first.x) {
+            format!("column {}", first.x + 1)
+        } else if group.coordinates.len() == self.group_size() && group.coordinates.iter().all(|c| c.x == c.y) {
+            String::from("main diagonal")
+        } else if group.coordinates.len() == self.group_size()
+            && group.coordinates.iter().all(|c| c.x + c.y == self.group_size() - 1) {
+            String::from("anti-diagonal")
+        } else {
+            format!("block at {}", cell_name(top_left(group)))
+        }
+    }
+}
+
+/// Escape `s` for use inside a JSON string.
+fn json_string(s: &str) -> String {
+    let mut result = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn json_cell(coord: &Coord) -> String {
+    format!("{{\"row\":{},\"column\":{}}}", coord.y + 1, coord.x + 1)
+}
+
+fn json_value(coord: &Coord, value: usize) -> String {
+    format!("{{\"row\":{},\"column\":{},\"value\":{}}}", coord.y + 1, coord.x + 1, value + 1)
+}
+
+fn json_array(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(","))
+}
+
+impl Step {
+    /// Describe this step in a single line, for example
+    /// `Hidden single in row 3: r3c5 = 7`.
+    pub fn describe(&self, board: &Board) -> String {
+        let group_size = board.group_size();
+        let mut result = String::from(self.technique.name());
+        if !self.groups.is_empty() {
+            let names: Vec<String> = self.groups.iter().map(|g| board.group_name(*g)).collect();
+            result.push_str(&format!(" in {}", names.join(", ")));
+        }
+        if !self.placements.is_empty() {
+            let placements: Vec<String> = self.placements.iter()
+                .map(|(c, v)| format!("{} = {}", cell_name(c), value_to_symbol(Some(*v), group_size)))
+                .collect();
+            result.push_str(&format!(": {}", placements.join(", ")));
+            return result;
+        }
+        if !self.cells.is_empty() {
+            result.push_str(&format!(" on {}", cell_list(&self.cells)));
+        }
+        // Group the eliminations by value, in order of first appearance.
+        let mut values: Vec<(usize, Vec<Coord>)> = Vec::new();
+        for (coord, value) in &self.eliminations {
+            match values.iter_mut().find(|(v, _)| v == value) {
+                Some((_, coords)) => coords.push(coord.clone()),
+                None => values.push((*value, vec![coord.clone()])),
+            }
+        }
+        let removals: Vec<String> = values.iter()
+            .map(|(v, coords)| format!("{} from {}", value_to_symbol(Some(*v), group_size), cell_list(coords)))
+            .collect();
+        result.push_str(&format!(": removes {}", removals.join("; ")));
+        result
+    }
+
+    /// Write this step as a JSON object.
+    pub fn to_json(&self, board: &Board) -> String {
+        let groups = self.groups.iter()
+            .map(|g| format!("{{\"index\":{},\"name\":{}}}", g, json_string(&board.group_name(*g))));
+        format!("{{\"technique\":{},\"description\":{},\"cells\":{},\"groups\":{},\"placements\":{},\"eliminations\":{}}}",
+                json_string(self.technique.name()),
+                json_string(&self.describe(board)),
+                json_array(self.cells.iter().map(json_cell)),
+                json_array(groups),
+                json_array(self.placements.iter().map(|(c, v)| json_value(c, *v))),
+                json_array(self.eliminations.iter().map(|(c, v)| json_value(c, *v))))
+    }
+}
+
+impl LogicalSolution {
+    /// Describe all steps of the logical solver, one numbered line per step.
+    pub fn explain(&self) -> String {
+        let mut result = String::new();
+        for (index, step) in self.steps.iter().enumerate() {
+            result.push_str(&format!("{}. {}\n", index + 1, step.describe(&self.board)));
+        }
+        if !self.is_solved() {
+            result.push_str("No technique applies anymore, the puzzle is not solved.\n");
+        }
+        result
+    }
+
+    /// Write all steps as a JSON object, with the steps in the `steps` array.
+    pub fn to_json(&self) -> String {
+        format!("{{\"solved\":{},\"steps\":{}}}\n",
+                self.is_solved(),
+                json_array(self.steps.iter().map(|s| s.to_json(&self.board))))
+    }
+}
@@ -0,0 +1,568 @@
+//! Textual representations of boards.
+//!
+//! Puzzles can be written as `x y value` triples, on a single line, row by
+//! row, or as a grid of rows with `|` and `-+-` separating the blocks.
+//!
+//! The triple format may start with header lines describing the board,
+//! `size 2x3` for the block width and height, `variant nrc` for the
+//! extra groups and `rule anti-knight` for each chess rule. Without
+//! headers a standard 9x9 board is assumed.
+//!
+//! Values are written 1-based. Boards with up to 35 values use a single
+//! character per cell: `1`-`9` followed by `A`-`Z`. Larger boards write
+//! every value as a decimal number, separated by whitespace. Empty cells
+//! are written as `.`, and `0` is accepted as empty cell as well.
+//!
+//! Killer cages are written as header lines as well, like `cage 15 1,1 2,1`
+//! for a cage with sum 15 and the cells at x 1, y 1 and at x 2, y 1.
+//!
+//! In the grid format, empty cells on the diagonals of a diagonal board are
+//! written as `\` and `/`, and as `*` where the diagonals cross. Reading a
+//! grid with these markers gives a diagonal board.
+
+use super::cage::Cage;
+use super::{ChessRule, Coord, Error, KillerBoard, RectangularBoard, Result, Variant};
+
+/// Highest `group_size` that is written with a single character per cell.
+const MAX_SINGLE_CHAR_SIZE: usize = 35;
+
+/// Returns whether boards of `group_size` need multi-character tokens.
+pub fn uses_tokens(group_size: usize) -> bool {
+    group_size > MAX_SINGLE_CHAR_SIZE
+}
+
+/// The symbol for the 0-based `value` on a board of `group_size`.
+pub fn value_to_symbol(value: Option<usize>, group_size: usize) -> String {
+    match value {
+        None => String::from("."),
+        Some(v) if uses_tokens(group_size) => format!("{}", v + 1),
+        Some(v) => std::char::from_digit((v + 1) as u32, 36)
+            .map(|c| c.to_ascii_uppercase().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Parse a single symbol, returning `Ok(None)` for an empty cell.
+///
+/// `line` and `column` are used for error reporting only.
+pub fn symbol_to_value(symbol: &str, group_size: usize, line: usize, column: usize) -> Result<Option<usize>> {
+    if symbol == "." || symbol == "0" {
+        return Ok(None);
+    }
+    let number = if uses_tokens(group_size) {
+        symbol.parse::<usize>().ok()
+    } else {
+        let mut chars = symbol.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => c.to_digit(36).map(|d| d as usize),
+            _ => None,
+        }
+    };
+    match number {
+        Some(n) if n >= 1 && n <= group_size => Ok(Some(n - 1)),
+        _ => Err(Error::Parse {
+            line,
+            column,
+            message: format!("'{}' is not a value between 1 and {}", symbol, group_size),
+        }),
+    }
+}
+
+/// Split a line in symbols, together with their 1-based column.
+///
+/// Single character boards ignore whitespace, while larger boards use it
+/// to separate the tokens.
+pub fn split_symbols(l: &str, group_size: usize) -> Vec<(usize, String)> {
+    let mut symbols = Vec::new();
+    let mut current = String::new();
+    let mut start = 0;
+    for (index, c) in l.chars().enumerate() {
+        if c.is_whitespace() {
+            if !current.is_empty() {
+                symbols.push((start + 1, current));
+                current = String::new();
+            }
+        } else if uses_tokens(group_size) {
+            if current.is_empty() {
+                start = index;
+            }
+            current.push(c);
+        } else {
+            symbols.push((index + 1, c.to_string()));
+        }
+    }
+    if !current.is_empty() {
+        symbols.push((start + 1, current));
+    }
+    symbols
+}
+
+/// Parse a line of three 1-based numbers, returning them 0-based.
+fn parse_triple(line: usize, l: &str) -> Result<[usize; 3]> {
+    let mut numbers = [0; 3];
+    let mut count = 0;
+    let mut column = 1;
+    for token in l.split(' ') {
+        if !token.is_empty() {
+            if count == 3 {
+                return Err(Error::Parse {
+                    line,
+                    column,
+                    message: String::from("expected exactly three numbers"),
+                });
+            }
+            let number = token.parse::<usize>().ok()
+                .and_then(|n| n.checked_sub(1))
+                .ok_or_else(|| Error::Parse {
+                    line,
+                    column,
+                    message: format!("expected a positive number, found '{}'", token),
+                })?;
+            numbers[count] = number;
+            count += 1;
+        }
+        column += token.chars().count() + 1;
+    }
+    if count != 3 {
+        return Err(Error::Parse {
+            line,
+            column: column - 1,
+            message: String::from("expected exactly three numbers"),
+        });
+    }
+    Ok(numbers)
+}
+
+/// Parse the block size of a `size` header, written as `<width>x<height>`.
+fn parse_block_size(line: usize, column: usize, size: &str) -> Result<(usize, usize)> {
+    let mut parts = size.split('x').map(|n| n.parse::<usize>().ok());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Some(w)), Some(Some(h)), None) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(Error::Parse {
+            line,
+            column,
+            message: format!("expected a block size like 3x3, found '{}'", size),
+        }),
+    }
+}
+
+/// Parse the cells of a `cage` header, written as 1-based `<x>,<y>` pairs.
+fn parse_cage_cell(line: usize, column: usize, cell: &str) -> Result<Coord> {
+    let mut parts = cell.split(',')
+        .map(|n| n.parse::<usize>().ok().and_then(|n| n.checked_sub(1)));
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Some(x)), Some(Some(y)), None) => Ok(Coord::new(x, y)),
+        _ => Err(Error::Parse {
+            line,
+            column,
+            message: format!("expected a cell like 1,2, found '{}'", cell),
+        }),
+    }
+}
+
+/// The board described by the header lines of the triple format.
+struct TripleHeader {
+    block_width: usize,
+    block_height: usize,
+    variant: Variant,
+    chess_rules: Vec<ChessRule>,
+    /// The cages, with the line they are described on.
+    cages: Vec<(usize, Cage)>,
+}
+
+impl TripleHeader {
+    fn parse_line(&mut self, line: usize, l: &str) -> Result<()> {
+        let words: Vec<&str> = l.split_whitespace().collect();
+        let column = l.find(|c: char| !c.is_whitespace()).unwrap_or(0) + 1;
+        let argument_column = words.get(1)
+            .and_then(|w| l.rfind(w))
+            .map_or(column, |index| index + 1);
+        match words.as_slice() {
+            ["size", size] => {
+                let (w, h) = parse_block_size(line, argument_column, size)?;
+                self.block_width = w;
+                self.block_height = h;
+            },
+            ["variant", name] => {
+                self.variant = Variant::from_name(name).ok_or_else(|| Error::Parse {
+                    line,
+                    column: argument_column,
+                    message: format!("unknown variant '{}'", name),
+                })?;
+            },
+            ["rule", name] => {
+                let rule = ChessRule::from_name(name).ok_or_else(|| Error::Parse {
+                    line,
+                    column: argument_column,
+                    message: format!("unknown rule '{}'", name),
+                })?;
+                self.chess_rules.push(rule);
+            },
+            ["cage", sum, cells @ ..] if !cells.is_empty() => {
+                // The 1-based column of every word, as the cells may repeat the sum.
+                let mut columns = Vec::new();
+                let mut previous = ' ';
+                for (index, c) in l.chars().enumerate() {
+                    if !c.is_whitespace() && previous.is_whitespace() {
+                        columns.push(index + 1);
+                    }
+                    previous = c;
+                }
+                let sum = sum.parse::<usize>().ok()
+                    .filter(|sum| *sum > 0)
+                    .ok_or_else(|| Error::Parse {
+                        line,
+                        column: columns[1],
+                        message: format!("expected a positive sum, found '{}'", sum),
+                    })?;
+                let coordinates = cells.iter().zip(&columns[2..])
+                    .map(|(cell, column)| parse_cage_cell(line, *column, cell))
+                    .collect::<Result<Vec<Coord>>>()?;
+                let cage = Cage::new(coordinates, sum).map_err(|e| Error::Parse {
+                    line,
+                    column: columns[2],
+                    message: e.to_string(),
+                })?;
+                self.cages.push((line, cage));
+            },
+            _ => return Err(Error::Parse {
+                line,
+                column,
+                message: format!("unknown header '{}'", l.trim()),
+            }),
+        }
+        Ok(())
+    }
+
+    fn create_board(&self, line: usize) -> Result<RectangularBoard> {
+        let board = RectangularBoard::with_variant(self.block_width, self.block_height, self.variant)
+            .map_err(|e| Error::Parse {
+                line,
+                column: 1,
+                message: e.to_string(),
+            })?;
+        Ok(self.chess_rules.iter().fold(board, |board, rule| board.with_chess_rule(*rule)))
+    }
+}
+
+/// Symbols marking empty cells on the diagonals in the grid format.
+const DIAGONAL_MARKERS: &[&str] = &["\\", "/", "*"];
+
+/// Characters that only separate blocks in the grid format.
+const GRID_SEPARATORS: &[char] = &['|', '-', '+', '='];
+
+/// A row of cells in the grid format, with the cells split per block.
+struct GridRow {
+    line: usize,
+    blocks: Vec<Vec<(usize, String)>>,
+}
+
+impl GridRow {
+    fn cell_count(&self) -> usize {
+        self.blocks.iter().map(|b| b.len()).sum()
+    }
+}
+
+/// Infer the missing block dimension(s) of a board of `group_size`.
+fn infer_block_size(group_size: usize, block_width: Option<usize>, block_height: Option<usize>) -> Result<(usize, usize)> {
+    let divide = |n: usize| if n > 0 && group_size.is_multiple_of(n) {
+        Some(group_size / n)
+    } else {
+        None
+    };
+    let square_root = (1..=group_size).find(|n| n * n == group_size);
+    let sizes = match (block_width, block_height) {
+        (Some(w), Some(h)) => Some((w, h)),
+        (Some(w), None) => divide(w).map(|h| (w, h)),
+        (None, Some(h)) => divide(h).map(|w| (w, h)),
+        (None, None) => square_root.map(|n| (n, n)),
+    };
+    match sizes {
+        Some((w, h)) if w * h == group_size => Ok((w, h)),
+        _ => Err(Error::Parse {
+            line: 1,
+            column: 1,
+            message: format!("cannot infer the block size of a board with {} rows", group_size),
+        }),
+    }
+}
+
+impl RectangularBoard {
+    /// Parse a puzzle from lines of `x y value` triples.
+    ///
+    /// All numbers are 1-based, blank lines are ignored. Header lines
+    /// describing the board must precede the triples.
+    pub fn from_string(s: &str) -> Result<Self> {
+        RectangularBoard::from_string_with_defaults(s, 3, 3, Variant::Standard)
+    }
+
+    /// Parse a puzzle from lines of `x y value` triples, using the given
+    /// geometry and variant unless the headers specify otherwise.
+    pub fn from_string_with_defaults(s: &str, block_width: usize, block_height: usize, variant: Variant) -> Result<Self> {
+        let mut header = TripleHeader {
+            block_width,
+            block_height,
+            variant,
+            chess_rules: Vec::new(),
+            cages: Vec::new(),
+        };
+        let board = RectangularBoard::parse_triples(s, &mut header)?;
+        match header.cages.first() {
+            Some((line, _)) => Err(Error::Parse {
+                line: *line,
+                column: 1,
+                message: String::from("cages need a killer board"),
+            }),
+            None => Ok(board),
+        }
+    }
+
+    /// Parse the headers and triples of `s`, on top of the defaults in `header`.
+    fn parse_triples(s: &str, header: &mut TripleHeader) -> Result<Self> {
+        let mut board: Option<RectangularBoard> = None;
+        let mut last_line = 1;
+        for (line_index, l) in s.lines().enumerate() {
+            let line = line_index + 1;
+            last_line = line;
+            if l.trim().is_empty() {
+                continue;
+            }
+            if l.trim_start().starts_with(char::is_alphabetic) {
+                if board.is_some() {
+                    return Err(Error::Parse {
+                        line,
+                        column: 1,
+                        message: String::from("headers must precede the values"),
+                    });
+                }
+                header.parse_line(line, l)?;
+                continue;
+            }
+            let numbers = parse_triple(line, l)?;
+            let board = match board.as_mut() {
+                Some(board) => board,
+                None => board.insert(header.create_board(line)?),
+            };
+            board.board.prefill_value(&Coord::new(numbers[0], numbers[1]), numbers[2])?;
+        }
+        match board {
+            Some(board) => Ok(board),
+            None => header.create_board(last_line),
+        }
+    }
+
+    /// Write the givens as `x y value` triples, preceded by the headers
+    /// needed to describe a board that is not a standard 9x9 board.
+    pub fn to_triples(&self) -> String {
+        let mut result = self.triple_headers();
+        self.write_triples(&mut result);
+        result
+    }
+
+    /// The headers describing the geometry, variant and chess rules, when
+    /// they differ from a standard 9x9 board.
+    fn triple_headers(&self) -> String {
+        let mut result = String::new();
+        if self.block_width != 3 || self.block_height != 3 {
+            result.push_str(&format!("size {}x{}\n", self.block_width, self.block_height));
+        }
+        if self.variant != Variant::Standard {
+            result.push_str(&format!("variant {}\n", self.variant.name()));
+        }
+        for rule in &self.chess_rules {
+            result.push_str(&format!("rule {}\n", rule.name()));
+        }
+        result
+    }
+
+    fn write_triples(&self, result: &mut String) {
+        let group_size = self.cells.group_size();
+        for y in 0..group_size {
+            for x in 0..group_size {
+                if let Some(v) = self.cells.get_cell(&Coord::new(x, y)).get_value() {
+                    result.push_str(&format!("{} {} {}\n", x + 1, y + 1, v + 1));
+                }
+            }
+        }
+    }
+
+    /// Parse a puzzle written on a single line, row by row.
+    ///
+    /// For a regular 9x9 puzzle this is the common 81 character format,
+    /// with `.` or `0` for empty cells.
+    pub fn from_line(s: &str, block_width: usize, block_height: usize) -> Result<Self> {
+        let mut board = RectangularBoard::with_variant(block_width, block_height, Variant::Standard)?;
+        let group_size = board.cells.group_size();
+        let l = s.trim_end_matches(['\r', '\n']);
+        if l.contains('\n') {
+            return Err(Error::Parse {
+                line: 2,
+                column: 1,
+                message: String::from("expected the puzzle on a single line"),
+            });
+        }
+        let symbols = split_symbols(l, group_size);
+        let expected = group_size * group_size;
+        if symbols.len() != expected {
+            let column = symbols.get(expected)
+                .map(|(column, _)| *column)
+                .unwrap_or_else(|| l.chars().count() + 1);
+            return Err(Error::Parse {
+                line: 1,
+                column,
+                message: format!("expected {} cells, found {}", expected, symbols.len()),
+            });
+        }
+        for (index, (column, symbol)) in symbols.iter().enumerate() {
+            if let Some(value) = symbol_to_value(symbol, group_size, 1, *column)? {
+                let coord = Coord::new(index % group_size, index / group_size);
+                board.board.prefill_value(&coord, value)?;
+            }
+        }
+        Ok(board)
+    }
+
+    /// Write the board on a single line, row by row.
+    pub fn to_line(&self) -> String {
+        let group_size = self.cells.group_size();
+        let mut symbols = Vec::with_capacity(group_size * group_size);
+        for y in 0..group_size {
+            for x in 0..group_size {
+                let value = self.cells.get_cell(&Coord::new(x, y)).get_value();
+                symbols.push(value_to_symbol(value, group_size));
+            }
+        }
+        if uses_tokens(group_size) {
+            symbols.join(" ")
+        } else {
+            symbols.concat()
+        }
+    }
+
+    /// Parse a puzzle laid out as a grid, one row per line.
+    ///
+    /// Whitespace and the separator characters `|`, `-`, `+` and `=` are
+    /// ignored, except that the block size is inferred from the position of
+    /// the first `|` and the first separator line. Without separators the
+    /// blocks are assumed to be square. Marked diagonals give a diagonal board.
+    pub fn from_grid(s: &str) -> Result<Self> {
+        let mut rows: Vec<GridRow> = Vec::new();
+        let mut block_height = None;
+        let mut lines = Vec::new();
+        for (index, l) in s.lines().enumerate() {
+            let content = l.replace(GRID_SEPARATORS, "");
+            if !content.trim().is_empty() {
+                lines.push((index + 1, l));
+            } else if l.contains(['-', '=']) && block_height.is_none() && !lines.is_empty() {
+                block_height = Some(lines.len());
+            }
+        }
+        let group_size = lines.len();
+        for (line, l) in lines {
+            let mut blocks = Vec::new();
+            let mut offset = 0;
+            for segment in l.split('|') {
+                let symbols: Vec<(usize, String)> = split_symbols(segment, group_size).into_iter()
+                    .filter(|(_, symbol)| !symbol.chars().all(|c| GRID_SEPARATORS.contains(&c)))
+                    .map(|(column, symbol)| (column + offset, symbol))
+                    .collect();
+                if !symbols.is_empty() {
+                    blocks.push(symbols);
+                }
+                offset += segment.chars().count() + 1;
+            }
+            rows.push(GridRow { line, blocks });
+        }
+        let block_width = rows.first()
+            .filter(|row| row.blocks.len() > 1)
+            .map(|row| row.blocks[0].len());
+        let (block_width, block_height) = infer_block_size(group_size, block_width, block_height)?;
+
+        let is_diagonal = rows.iter()
+            .flat_map(|row| row.blocks.iter().flatten())
+            .any(|(_, symbol)| DIAGONAL_MARKERS.contains(&symbol.as_str()));
+        let variant = if is_diagonal { Variant::Diagonal } else { Variant::Standard };
+        let mut board = RectangularBoard::with_variant(block_width, block_height, variant)?;
+        for (y, row) in rows.iter().enumerate() {
+            if row.cell_count() != group_size {
+                return Err(Error::Parse {
+                    line: row.line,
+                    column: 1,
+                    message: format!("expected {} cells, found {}", group_size, row.cell_count()),
+                });
+            }
+            for (x, (column, symbol)) in row.blocks.iter().flatten().enumerate() {
+                if DIAGONAL_MARKERS.contains(&symbol.as_str()) {
+                    continue;
+                }
+                if let Some(value) = symbol_to_value(symbol, group_size, row.line, *column)? {
+                    board.board.prefill_value(&Coord::new(x, y), value)?;
+                }
+            }
+        }
+        Ok(board)
+    }
+
+    /// Write the board as a compact grid, with `|` and `-+-` separating the blocks.
+    pub fn to_grid(&self) -> String {
+        let group_size = self.cells.group_size();
+        let width = value_to_symbol(Some(group_size - 1), group_size).len();
+        let block_count = group_size / self.block_width;
+        let separator = vec!["-".repeat(self.block_width * (width + 1) - 1); block_count].join("-+-");
+        let mut result = String::new();
+        for y in 0..group_size {
+            if y > 0 && y % self.block_height == 0 {
+                result.push_str(&separator);
+                result.push('\n');
+            }
+            let blocks: Vec<String> = (0..block_count)
+                .map(|xx| {
+                    (0..self.block_width)
+                        .map(|x| {
+                            let coord = Coord::new(xx * self.block_width + x, y);
+                            let symbol = match (self.cells.get_cell(&coord).get_value(), self.diagonal_marker(&coord)) {
+                                (None, Some(marker)) => String::from(marker),
+                                (value, _) => value_to_symbol(value, group_size),
+                            };
+                            format!("{:>width$}", symbol, width = width)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect();
+            result.push_str(&blocks.join(" | "));
+            result.push('\n');
+        }
+        result
+    }
+}
+
+impl KillerBoard {
+    /// Parse a killer puzzle from the triple format, with a `cage` header
+    /// line for every cage.
+    pub fn from_string(s: &str) -> Result<Self> {
+        let mut header = TripleHeader {
+            block_width: 3,
+            block_height: 3,
+            variant: Variant::Standard,
+            chess_rules: Vec::new(),
+            cages: Vec::new(),
+        };
+        let board = RectangularBoard::parse_triples(s, &mut header)?;
+        KillerBoard::new(&board, header.cages.into_iter().map(|(_, cage)| cage).collect())
+    }
+
+    /// Write the board in the triple format, with a `cage` header line for every cage.
+    pub fn to_triples(&self) -> String {
+        let mut result = self.triple_headers();
+        for cage in self.cages.iter() {
+            result.push_str(&format!("cage {}", cage.sum));
+            for coord in &cage.coordinates {
+                result.push_str(&format!(" {},{}", coord.x + 1, coord.y + 1));
+            }
+            result.push('\n');
+        }
+        self.write_triples(&mut result);
+        result
+    }
+}
@@ -0,0 +1,175 @@
+//! Generating puzzles with a unique solution.
+//!
+//! A random solution is found by searching the board with the possible
+//! values tried in a random order. Then its values are removed one at a
+//! time, in a random order, as long as the puzzle keeps a unique solution.
+//! Symmetric puzzles remove the values of a whole orbit of the symmetry at
+//! a time, so the remaining givens keep the symmetry.
+
+use super::board::Board;
+use super::coord::Coord;
+use super::random::Random;
+use super::RectangularBoard;
+
+/// A symmetry of the pattern of givens of a puzzle.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Symmetry {
+    None,
+    /// Rotating the board by 180 degrees.
+    Rotational180,
+    /// Rotating the board by 90 degrees.
+    Rotational90,
+    /// Mirroring the top and bottom halves.
+    Horizontal,
+    /// Mirroring the left and right halves.
+    Vertical,
+    /// Mirroring in the diagonal from the top left to the bottom right.
+    Diagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 6] = [
+        Symmetry::None,
+        Symmetry::Rotational180,
+        Symmetry::Rotational90,
+        Symmetry::Horizontal,
+        Symmetry::Vertical,
+        Symmetry::Diagonal,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Symmetry::None => "none",
+            Symmetry::Rotational180 => "rotational180",
+            Symmetry::Rotational90 => "rotational90",
+            Symmetry::Horizontal => "horizontal",
+            Symmetry::Vertical => "vertical",
+            Symmetry::Diagonal => "diagonal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Symmetry::ALL.iter().find(|s| s.name() == name.to_ascii_lowercase()).cloned()
+    }
+
+    /// The cell `coord` maps to, on a board of `group_size` by `group_size` cells.
+    pub fn image(&self, coord: &Coord, group_size: usize) -> Coord {
+        let last = group_size - 1;
+        match self {
+            Symmetry::None => coord.clone(),
+            Symmetry::Rotational180 => Coord::new(last - coord.x, last - coord.y),
+            Symmetry::Rotational90 => Coord::new(last - coord.y, coord.x),
+            Symmetry::Horizontal => Coord::new(coord.x, last - coord.y),
+            Symmetry::Vertical => Coord::new(last - coord.x, coord.y),
+            Symmetry::Diagonal => Coord::new(coord.y, coord.x),
+        }
+    }
+
+    /// All cells `coord` maps to by applying this symmetry repeatedly, starting with `coord`.
+    pub fn orbit(&self, coord: &Coord, group_size: usize) -> Vec<Coord> {
+        let mut orbit = vec![coord.clone()];
+        loop {
+            let next = self.image(orbit.last().unwrap(), group_size);
+            if next == *coord {
+                return orbit;
+            }
+            orbit.push(next);
+        }
+    }
+}
+
+/// Fill in the values of `solution` at `clues` on the cells of `template`.
+fn puzzle_from(template: &Board, solution: &Board, clues: &[Coord]) -> Board {
+    let mut puzzle = template.clone();
+    for coord in clues {
+        let value = solution.get_cell(coord).get_value()
+            .expect("solutions have a value in every cell");
+        puzzle.prefill_value(coord, value)
+            .expect("values of a solution do not conflict");
+    }
+    puzzle
+}
+
+/// The empty cells of `board`, where clues can be placed.
+fn open_cells(board: &Board) -> Vec<Coord> {
+    let group_size = board.group_size();
+    (0..group_size)
+        .flat_map(|y| (0..group_size).map(move |x| Coord::new(x, y)))
+        .filter(|c| board.get_cell(c).get_value().is_none())
+        .collect()
+}
+
+/// Remove clues from a full `solution` of `template`, in the order of
+/// `candidates`, as long as the puzzle keeps a unique solution.
+///
+/// Every candidate is a list of cells that is removed at once.
+fn remove_clues(template: &Board, solution: &Board, candidates: &[Vec<Coord>]) -> Board {
+    let mut clues: Vec<Coord> = candidates.iter().flatten().cloned().collect();
+    for candidate in candidates {
+        let remaining: Vec<Coord> = clues.iter()
+            .filter(|c| !candidate.contains(c))
+            .cloned()
+            .collect();
+        if puzzle_from(template, solution, &remaining).count_solutions_up_to(2) == 1 {
+            clues = remaining;
+        }
+    }
+    puzzle_from(template, solution, &clues)
+}
+
+impl RectangularBoard {
+    /// Generate a puzzle with a unique solution, with the geometry and
+    /// variant of this board.
+    ///
+    /// Values already filled in on this board are kept as givens, so an
+    /// empty board gives a completely random puzzle. The same `seed` always
+    /// gives the same puzzle. Returns `None` when this board has no solution.
+    ///
+    /// No clue of the result can be removed without losing the unique
+    /// solution.
+    pub fn generate(&self, seed: u64) -> Option<Self> {
+        self.generate_symmetric(seed, Symmetry::None)
+    }
+
+    /// Generate a puzzle like `generate`, with givens that have `symmetry`.
+    ///
+    /// Values already filled in on this board are kept, so the result only
+    /// has the symmetry when these values have it as well. No orbit of
+    /// givens can be removed from the result without losing the unique
+    /// solution.
+    pub fn generate_symmetric(&self, seed: u64, symmetry: Symmetry) -> Option<Self> {
+        let mut random = Random::new(seed);
+        let solution = self.board.shuffled_solutions(random.next_u64()).next()?;
+        let group_size = self.group_size();
+        let cells = open_cells(&self.board);
+        let mut orbits: Vec<Vec<Coord>> = Vec::new();
+        for coord in &cells {
+            if !orbits.iter().any(|o| o.contains(coord)) {
+                let orbit = symmetry.orbit(coord, group_size).into_iter()
+                    .filter(|c| cells.contains(c))
+                    .collect();
+                orbits.push(orbit);
+            }
+        }
+        random.shuffle(&mut orbits);
+        Some(self.with_board(remove_clues(&self.board, &solution, &orbits)))
+    }
+}
+
+impl Board {
+    /// The symmetries of the pattern of values filled in on this board,
+    /// other than `Symmetry::None`.
+    pub fn symmetries(&self) -> Vec<Symmetry> {
+        let group_size = self.group_size();
+        let is_filled = |c: &Coord| self.get_cell(c).get_value().is_some();
+        Symmetry::ALL.iter()
+            .filter(|s| **s != Symmetry::None)
+            .filter(|s| {
+                (0..group_size)
+                    .flat_map(|y| (0..group_size).map(move |x| Coord::new(x, y)))
+                    .all(|c| is_filled(&c) == is_filled(&s.image(&c, group_size)))
+            })
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,132 @@
+//! Grading the difficulty of puzzles.
+//!
+//! A puzzle is rated by the hardest technique the logical solver needs,
+//! on a scale modelled after the ratings of Sudoku Explainer: from 1.5
+//! for puzzles solved with hidden singles only, to 6.5 for colouring.
+//! Puzzles the techniques cannot solve need trial and error, and are
+//! rated from 7.0 up to 11.9 by the depth of guessing alone. The size of
+//! the search is reported in the grade, but does not change the rating.
+
+use super::board::{Board, SearchStatistics};
+use super::error::Result;
+use super::logic::Technique;
+
+/// The rating of puzzles that need a single level of guessing.
+const GUESSING_RATING: f32 = 7.0;
+
+/// The rating added for every further level of guessing.
+const GUESSING_DEPTH_RATING: f32 = 0.5;
+
+/// The highest rating, as on the Sudoku Explainer scale.
+const MAX_RATING: f32 = 11.9;
+
+/// The kinds of techniques, from easiest to hardest.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum TechniqueFamily {
+    Singles,
+    Intersections,
+    Subsets,
+    Fish,
+    Wings,
+    Chains,
+}
+
+impl TechniqueFamily {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TechniqueFamily::Singles => "singles",
+            TechniqueFamily::Intersections => "intersections",
+            TechniqueFamily::Subsets => "subsets",
+            TechniqueFamily::Fish => "fish",
+            TechniqueFamily::Wings => "wings",
+            TechniqueFamily::Chains => "chains",
+        }
+    }
+}
+
+impl Technique {
+    pub fn family(&self) -> TechniqueFamily {
+        match self {
+            Technique::HiddenSingle | Technique::NakedSingle => TechniqueFamily::Singles,
+            Technique::PointingPair | Technique::BoxLineReduction => TechniqueFamily::Intersections,
+            Technique::NakedPair | Technique::HiddenPair | Technique::NakedTriple
+            | Technique::HiddenTriple | Technique::NakedQuad | Technique::HiddenQuad => TechniqueFamily::Subsets,
+            Technique::XWing | Technique::Swordfish => TechniqueFamily::Fish,
+            Technique::XYWing => TechniqueFamily::Wings,
+            Technique::SimpleColouring => TechniqueFamily::Chains,
+        }
+    }
+
+    /// The rating of this technique, following Sudoku Explainer where it
+    /// has the same technique.
+    pub fn rating(&self) -> f32 {
+        match self {
+            Technique::HiddenSingle => 1.5,
+            Technique::NakedSingle => 2.3,
+            Technique::PointingPair => 2.6,
+            Technique::BoxLineReduction => 2.8,
+            Technique::NakedPair => 3.0,
+            Technique::XWing => 3.2,
+            Technique::HiddenPair => 3.4,
+            Technique::NakedTriple => 3.6,
+            Technique::Swordfish => 3.8,
+            Technique::HiddenTriple => 4.0,
+            Technique::XYWing => 4.2,
+            Technique::NakedQuad => 5.0,
+            Technique::HiddenQuad => 5.4,
+            Technique::SimpleColouring => 6.5,
+        }
+    }
+}
+
+/// The difficulty of a puzzle.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Grade {
+    /// The hardest technique used by the logical solver, if it made any steps.
+    pub hardest_technique: Option<Technique>,
+    /// All techniques used by the logical solver, from easiest to hardest.
+    pub techniques: Vec<Technique>,
+    pub rating: f32,
+    /// The number of steps the logical solver made.
+    pub steps: usize,
+    /// Whether the techniques solve the puzzle without guessing.
+    pub solved_logically: bool,
+    /// The search for the remaining values, from where the techniques got
+    /// stuck. Empty when the puzzle is solved logically.
+    pub search: SearchStatistics,
+}
+
+impl Grade {
+    pub fn needs_guessing(&self) -> bool {
+        !self.solved_logically
+    }
+}
+
+impl Board {
+    /// Grade the difficulty of solving this board.
+    ///
+    /// The search stops at a second solution, so puzzles without a unique
+    /// solution are graded as well.
+    pub fn grade(&self) -> Result<Grade> {
+        let solution = self.solve_logically()?;
+        let hardest_technique = solution.hardest_technique();
+        let mut techniques: Vec<Technique> = solution.steps.iter().map(|s| s.technique).collect();
+        techniques.sort();
+        techniques.dedup();
+        let mut rating = hardest_technique.map_or(0.0, |t| t.rating());
+        let mut search = SearchStatistics::default();
+        if !solution.is_solved() {
+            search = solution.board.search_statistics(2);
+            let depth = search.depth.max(1) - 1;
+            rating = MAX_RATING.min(GUESSING_RATING + GUESSING_DEPTH_RATING * depth as f32);
+        }
+        Ok(Grade {
+            hardest_technique,
+            techniques,
+            rating,
+            steps: solution.steps.len(),
+            solved_logically: solution.is_solved(),
+            search,
+        })
+    }
+}
@@ -1,4 +1,8 @@
+use super::board::Board;
+use super::cell::Cell;
+use super::constraint::Constraint;
 use super::coord::Coord;
+use super::error::{Error, Result};
 
 #[derive(Clone, Debug)]
 pub struct Group {
@@ -16,3 +20,45 @@ impl Group {
         self.coordinates.contains(coord)
     }
 }
+
+/// All values in a group are different.
+///
+/// `Board::set_value` already strikes a placed value from all peers, so
+/// boards do not prune their groups while solving, and pruning only finds
+/// values that were filled in some other way.
+impl Constraint for Group {
+    fn prune(&self, board: &mut Board) -> Result<bool> {
+        let mut pruned = false;
+        for coord in &self.coordinates {
+            let value = match board.get_cell(coord).get_value() {
+                Some(value) => value,
+                None => continue,
+            };
+            for other in self.coordinates.iter().filter(|c| *c != coord) {
+                let cell = board.get_cell(other);
+                if cell.get_value() == Some(value) {
+                    return Err(Error::Conflict { coord: other.clone(), value });
+                }
+                if cell.is_possible(value) {
+                    board.strike_through(other, value)?;
+                    pruned = true;
+                }
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Groups smaller than `group_size`, like cages, leave the remaining
+    /// values unused.
+    fn is_satisfied(&self, board: &Board) -> bool {
+        let mut validation_cell = Cell::new(board.group_size());
+        for coord in &self.coordinates {
+            if let Some(value) = board.get_cell(coord).get_value() {
+                if validation_cell.strike_through(value).is_err() {
+                    return false;
+                }
+            }
+        }
+        validation_cell.possibilities() == board.group_size() - self.coordinates.len()
+    }
+}
@@ -0,0 +1,246 @@
+//! Jigsaw puzzles, where the blocks are irregular regions.
+//!
+//! The regions are described by a region map: one line per row, with a
+//! symbol per cell naming its region, like `AAABBBCCC`. Whitespace is
+//! ignored. Every region must have one cell for every value, and its cells
+//! must be connected horizontally or vertically.
+
+use std::collections::VecDeque;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use super::board::Board;
+use super::cell_container::CellContainer;
+use super::coord::Coord;
+use super::error::{Error, Result};
+use super::format::{split_symbols, symbol_to_value, uses_tokens, value_to_symbol};
+use super::group::Group;
+use super::outline::pretty_print_outlined;
+use super::{BoardPrinter, MAX_GROUP_SIZE};
+
+/// A board with rows, columns and irregular regions.
+#[derive(Clone)]
+pub struct JigsawBoard {
+    /// The symbol naming each region, in order of first appearance.
+    region_names: Arc<[char]>,
+    /// The index of the region of every cell, row by row.
+    region_of: Arc<[usize]>,
+    board: Board,
+}
+
+impl Deref for JigsawBoard {
+    type Target = Board;
+
+    fn deref(&self) -> &Board {
+        &self.board
+    }
+}
+
+impl JigsawBoard {
+    /// Create an empty board with the regions of `region_map`.
+    pub fn new(region_map: &str) -> Result<Self> {
+        let rows: Vec<(usize, Vec<(usize, char)>)> = region_map.lines().enumerate()
+            .map(|(index, l)| {
+                let symbols = l.chars().enumerate()
+                    .filter(|(_, c)| !c.is_whitespace())
+                    .map(|(column, c)| (column + 1, c))
+                    .collect::<Vec<_>>();
+                (index + 1, symbols)
+            })
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .collect();
+        let group_size = rows.len();
+        if group_size == 0 {
+            return Err(Error::Geometry {
+                message: String::from("the region map is empty"),
+            });
+        }
+        if group_size > MAX_GROUP_SIZE {
+            return Err(Error::Geometry {
+                message: format!("the region map has more than {} rows", MAX_GROUP_SIZE),
+            });
+        }
+        let mut region_names: Vec<char> = Vec::new();
+        let mut region_of = Vec::with_capacity(group_size * group_size);
+        for (line, symbols) in &rows {
+            if symbols.len() != group_size {
+                let column = symbols.get(group_size).map_or(1, |(column, _)| *column);
+                return Err(Error::Parse {
+                    line: *line,
+                    column,
+                    message: format!("expected {} regions, found {}", group_size, symbols.len()),
+                });
+            }
+            for (_, symbol) in symbols {
+                let region = match region_names.iter().position(|n| n == symbol) {
+                    Some(region) => region,
+                    None => {
+                        region_names.push(*symbol);
+                        region_names.len() - 1
+                    },
+                };
+                region_of.push(region);
+            }
+        }
+        if region_names.len() != group_size {
+            return Err(Error::Geometry {
+                message: format!("expected {} regions, found {}", group_size, region_names.len()),
+            });
+        }
+        let regions: Vec<Vec<Coord>> = (0..group_size)
+            .map(|region| {
+                (0..group_size * group_size)
+                    .filter(|index| region_of[*index] == region)
+                    .map(|index| Coord::new(index % group_size, index / group_size))
+                    .collect()
+            })
+            .collect();
+        for (region, coords) in regions.iter().enumerate() {
+            if coords.len() != group_size {
+                return Err(Error::Geometry {
+                    message: format!("region '{}' has {} cells, expected {}",
+                                     region_names[region], coords.len(), group_size),
+                });
+            }
+            if !is_connected(coords) {
+                return Err(Error::Geometry {
+                    message: format!("region '{}' is not connected", region_names[region]),
+                });
+            }
+        }
+
+        let mut groups = Vec::new();
+        for x in 0..group_size {
+            groups.push(Group::new((0..group_size).map(|y| Coord::new(x, y)).collect()));
+        }
+        for y in 0..group_size {
+            groups.push(Group::new((0..group_size).map(|x| Coord::new(x, y)).collect()));
+        }
+        groups.extend(regions.into_iter().map(Group::new));
+        Ok(JigsawBoard {
+            region_names: region_names.into(),
+            region_of: region_of.into(),
+            board: Board::new(CellContainer::new(group_size), groups),
+        })
+    }
+
+    /// Create a puzzle with the regions of `region_map`, and the givens of
+    /// `puzzle` written on a single line, row by row.
+    pub fn from_line(region_map: &str, puzzle: &str) -> Result<Self> {
+        let mut board = JigsawBoard::new(region_map)?;
+        let group_size = board.group_size();
+        let symbols = split_symbols(puzzle.trim_end_matches(['\r', '\n']), group_size);
+        let expected = group_size * group_size;
+        if symbols.len() != expected {
+            return Err(Error::Parse {
+                line: 1,
+                column: symbols.get(expected).map_or(puzzle.chars().count() + 1, |(column, _)| *column),
+                message: format!("expected {} cells, found {}", expected, symbols.len()),
+            });
+        }
+        for (index, (column, symbol)) in symbols.iter().enumerate() {
+            if let Some(value) = symbol_to_value(symbol, group_size, 1, *column)? {
+                board.board.prefill_value(&Coord::new(index % group_size, index / group_size), value)?;
+            }
+        }
+        Ok(board)
+    }
+
+    /// Parse a region map, followed by a blank line and the puzzle on a
+    /// single line, row by row.
+    pub fn from_string(s: &str) -> Result<Self> {
+        let lines: Vec<&str> = s.lines().collect();
+        let blank = lines.iter().position(|l| l.trim().is_empty()).unwrap_or(lines.len());
+        let region_map = lines[..blank].join("\n");
+        match lines.iter().skip(blank).position(|l| !l.trim().is_empty()) {
+            Some(offset) => {
+                let puzzle = lines[blank + offset..].join("\n");
+                JigsawBoard::from_line(&region_map, puzzle.trim()).map_err(|e| match e {
+                    Error::Parse { column, message, .. } => Error::Parse { line: blank + offset + 1, column, message },
+                    e => e,
+                })
+            },
+            None => JigsawBoard::new(&region_map),
+        }
+    }
+
+    /// The symbol naming the region of `coord` in the region map.
+    pub fn region_name(&self, coord: &Coord) -> char {
+        self.region_names[self.region(coord)]
+    }
+
+    fn region(&self, coord: &Coord) -> usize {
+        self.region_of[coord.y * self.group_size() + coord.x]
+    }
+
+    /// Write the region map, one row per line.
+    pub fn to_region_map(&self) -> String {
+        let group_size = self.group_size();
+        let mut result = String::new();
+        for y in 0..group_size {
+            result.extend((0..group_size).map(|x| self.region_name(&Coord::new(x, y))));
+            result.push('\n');
+        }
+        result
+    }
+
+    /// Write the board on a single line, row by row.
+    pub fn to_line(&self) -> String {
+        let group_size = self.group_size();
+        (0..group_size * group_size)
+            .map(|index| {
+                let value = self.get_cell(&Coord::new(index % group_size, index / group_size)).get_value();
+                value_to_symbol(value, group_size)
+            })
+            .collect::<Vec<_>>()
+            .join(if uses_tokens(group_size) { " " } else { "" })
+    }
+
+    /// Iterate lazily over the solutions of this board, see `Board::solutions`.
+    pub fn solutions(&self) -> impl Iterator<Item = JigsawBoard> {
+        let region_names = Arc::clone(&self.region_names);
+        let region_of = Arc::clone(&self.region_of);
+        self.board.solutions()
+            .map(move |board| {
+                JigsawBoard {
+                    region_names: Arc::clone(&region_names),
+                    region_of: Arc::clone(&region_of),
+                    board,
+                }
+            })
+    }
+
+    pub fn has_unique_solution(&self) -> bool {
+        self.count_solutions_up_to(2) == 1
+    }
+
+    pub fn pretty_print(&self) {
+        println!("{}", BoardPrinter::pretty_print(self));
+    }
+}
+
+/// Whether `coords` form a single area of horizontally or vertically adjacent cells.
+pub fn is_connected(coords: &[Coord]) -> bool {
+    let mut reached = vec![false; coords.len()];
+    let mut queue = VecDeque::new();
+    reached[0] = true;
+    queue.push_back(0);
+    while let Some(index) = queue.pop_front() {
+        let current = &coords[index];
+        for (next, coord) in coords.iter().enumerate() {
+            if !reached[next] && current.x.abs_diff(coord.x) + current.y.abs_diff(coord.y) == 1 {
+                reached[next] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    reached.iter().all(|r| *r)
+}
+
+impl BoardPrinter for JigsawBoard {
+    /// Print the board in a grid, with borders around the regions. Once
+    /// values are found, the givens are highlighted in brackets.
+    fn pretty_print(&self) -> String {
+        pretty_print_outlined(&self.board, |c| Some(self.region(c)), |_| None)
+    }
+}
@@ -0,0 +1,84 @@
+//! Killer sudoku, where the values in each cage add up to the sum of the cage.
+//!
+//! The cages come on top of the groups of a rectangular board of any
+//! variant. Every cell is in at most one cage, and the cells of a cage are
+//! connected horizontally or vertically.
+
+use std::ops::Deref;
+
+use super::board::Board;
+use super::cage::Cage;
+use super::cell_container::CellContainer;
+use super::coord::Coord;
+use super::error::{Error, Result};
+use super::explain::cell_name;
+use super::jigsaw::is_connected;
+use super::outline::pretty_print_outlined;
+use super::{BoardPrinter, RectangularBoard};
+
+/// A rectangular board with killer cages.
+pub struct KillerBoard {
+    board: RectangularBoard,
+}
+
+impl Deref for KillerBoard {
+    type Target = RectangularBoard;
+
+    fn deref(&self) -> &RectangularBoard {
+        &self.board
+    }
+}
+
+impl KillerBoard {
+    /// Add `cages` to the geometry, variant, chess rules and other
+    /// constraints of `board`, keeping its givens.
+    pub fn new(board: &RectangularBoard, cages: Vec<Cage>) -> Result<Self> {
+        let groups = board.layout_groups().to_vec();
+        let killer = Board::with_cages(CellContainer::new(board.group_size()), groups, cages)?;
+        if let Some(cage) = killer.cages.iter().find(|cage| !is_connected(&cage.coordinates)) {
+            return Err(Error::Geometry {
+                message: format!("cage at {} is not connected", cell_name(cage.top_left())),
+            });
+        }
+        let killer = killer.with_constraints_of(board)
+            .with_givens(board.givens().iter())?;
+        Ok(KillerBoard {
+            board: board.with_board(killer),
+        })
+    }
+
+    /// The index in `cages` of the cage containing `coord`.
+    pub fn cage_of(&self, coord: &Coord) -> Option<usize> {
+        self.cages.iter().position(|cage| cage.contains_coord(coord))
+    }
+
+    /// The original puzzle, with only the givens of this board filled in.
+    pub fn puzzle(&self) -> Self {
+        KillerBoard {
+            board: self.board.puzzle(),
+        }
+    }
+
+    /// Iterate lazily over the solutions of this board, see `Board::solutions`.
+    pub fn solutions(&self) -> impl Iterator<Item = KillerBoard> {
+        self.board.solutions()
+            .map(|board| KillerBoard { board })
+    }
+
+    pub fn pretty_print(&self) {
+        println!("{}", BoardPrinter::pretty_print(self));
+    }
+}
+
+impl BoardPrinter for KillerBoard {
+    /// Print the board in a grid, with the outline of every cage and its
+    /// sum in the top left corner. Once values are found, the givens are
+    /// highlighted in brackets.
+    fn pretty_print(&self) -> String {
+        pretty_print_outlined(&self.board, |c| self.cage_of(c), |c| {
+            self.cages.iter()
+                .find(|cage| cage.top_left() == c)
+                .map(|cage| cage.sum.to_string())
+        })
+    }
+}
@@ -2,27 +2,93 @@
 //!
 //! A sudoku solver in Rust.
 
+pub mod batch;
 mod repeater;
+mod candidates;
 mod cell;
 mod coord;
+mod error;
+mod format;
 mod group;
+mod constraint;
+mod chess;
 mod cell_container;
 mod board;
+mod peer_index;
+mod logic;
+mod techniques;
+mod explain;
+mod grade;
+mod random;
+mod generate;
+mod difficulty;
+mod minimal;
+mod jigsaw;
+mod outline;
+mod cage;
+mod killer;
 
 use std::ops::Deref;
 
-use board::Board;
-use coord::Coord;
 use group::Group;
 use cell_container::CellContainer;
 
+pub use board::{Board, SearchStatistics, Solutions};
+pub use cage::Cage;
+pub use chess::ChessRule;
+pub use constraint::Constraint;
+pub use coord::Coord;
+pub use difficulty::{DifficultyTarget, Progress};
+pub use error::{Error, Result};
+pub use generate::Symmetry;
+pub use jigsaw::JigsawBoard;
+pub use killer::KillerBoard;
+pub use grade::{Grade, TechniqueFamily};
+pub use logic::{LogicalSolution, LogicalSolver, Step, Technique};
+
 trait BoardPrinter {
     fn pretty_print(&self) -> String;
 }
 
+/// The extra groups of a board, on top of its rows, columns and blocks.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Variant {
+    Standard,
+    /// The four extra windows of NRC puzzles, only for 9x9 boards.
+    Nrc,
+    /// Both main diagonals, as in X-Sudoku.
+    Diagonal,
+}
+
+impl Variant {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::Nrc => "nrc",
+            Variant::Diagonal => "diagonal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "standard" => Some(Variant::Standard),
+            "nrc" => Some(Variant::Nrc),
+            "diagonal" => Some(Variant::Diagonal),
+            _ => None,
+        }
+    }
+}
+
+/// The largest number of values of a board. Every cell keeps a list of
+/// its peers, so the memory of a board grows with the cube of this size.
+pub const MAX_GROUP_SIZE: usize = 256;
+
 pub struct RectangularBoard {
     block_width: usize,
     block_height: usize,
+    variant: Variant,
+    /// The chess rules on top of the groups, see `with_chess_rule`.
+    chess_rules: Vec<ChessRule>,
     board: Board,
 }
 
@@ -81,14 +147,107 @@ impl RectangularBoard {
         RectangularBoard {
             block_width,
             block_height,
+            variant: Variant::Standard,
+            chess_rules: Vec::new(),
+            board,
+        }
+    }
+
+    /// Create a board of the given geometry and variant.
+    pub fn with_variant(block_width: usize, block_height: usize, variant: Variant) -> Result<Self> {
+        if block_width == 0 || block_height == 0 {
+            return Err(Error::Geometry {
+                message: format!("block size {}x{} is empty", block_width, block_height),
+            });
+        }
+        match block_width.checked_mul(block_height) {
+            Some(group_size) if group_size <= MAX_GROUP_SIZE => (),
+            _ => return Err(Error::Geometry {
+                message: format!("block size {}x{} has more than {} values", block_width, block_height, MAX_GROUP_SIZE),
+            }),
+        }
+        match variant {
+            Variant::Standard => Ok(RectangularBoard::new(block_width, block_height)),
+            Variant::Nrc if block_width == 3 && block_height == 3 => Ok(RectangularBoard::new_nrc()),
+            Variant::Nrc => Err(Error::Geometry {
+                message: String::from("the nrc variant requires 3x3 blocks"),
+            }),
+            Variant::Diagonal => Ok(RectangularBoard::new_diagonal(block_width, block_height)),
+        }
+    }
+
+    /// Create a board of another variant with the same chess rules and other
+    /// constraints, and the same values as givens.
+    pub fn to_variant(&self, variant: Variant) -> Result<Self> {
+        let mut board = RectangularBoard::with_variant(self.block_width, self.block_height, variant)?;
+        board.board = board.board.with_constraints_of(&self.board);
+        board.chess_rules = self.chess_rules.clone();
+        let group_size = self.cells.group_size();
+        for y in 0..group_size {
+            for x in 0..group_size {
+                let coord = Coord::new(x, y);
+                if let Some(value) = self.get_cell(&coord).get_value() {
+                    board.board.prefill_value(&coord, value)?;
+                }
+            }
+        }
+        Ok(board)
+    }
+
+    /// A board with the geometry, variant and chess rules of this board,
+    /// and the cells of `board`.
+    fn with_board(&self, board: Board) -> Self {
+        RectangularBoard {
+            block_width: self.block_width,
+            block_height: self.block_height,
+            variant: self.variant,
+            chess_rules: self.chess_rules.clone(),
             board,
         }
     }
 
+    /// The original puzzle, with only the givens of this board filled in.
+    pub fn puzzle(&self) -> Self {
+        self.with_board(self.board.puzzle())
+    }
+
+    /// A board with the same geometry, variant and cells, and `constraint` added.
+    pub fn with_constraint(&self, constraint: impl Constraint + 'static) -> Self {
+        self.with_board(self.board.with_constraint(constraint))
+    }
+
+    /// A board with the same geometry, variant and cells, where equal
+    /// values may not be a chess move apart as well. Adding a rule twice
+    /// has no effect.
+    pub fn with_chess_rule(&self, rule: ChessRule) -> Self {
+        if self.chess_rules.contains(&rule) {
+            return self.with_board(self.board.clone());
+        }
+        let mut board = self.with_constraint(rule);
+        board.chess_rules.push(rule);
+        board
+    }
+
+    pub fn chess_rules(&self) -> &[ChessRule] {
+        &self.chess_rules
+    }
+
+    pub fn block_width(&self) -> usize {
+        self.block_width
+    }
+
+    pub fn block_height(&self) -> usize {
+        self.block_height
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
     pub fn new_nrc() -> Self {
         let board = RectangularBoard::new(3, 3);
         let cells = board.board.cells;
-        let mut groups = board.board.groups;
+        let mut groups = board.board.groups.to_vec();
         for xx in 0..2 {
             for yy in 0..2 {
                 let mut coords = Vec::new();
@@ -103,6 +262,8 @@ impl RectangularBoard {
         RectangularBoard {
             block_width: 3,
             block_height: 3,
+            variant: Variant::Nrc,
+            chess_rules: Vec::new(),
             board: Board::new(
                 cells,
                 groups,
@@ -110,15 +271,41 @@ impl RectangularBoard {
         }
     }
 
-    pub fn from_string(s: &str) -> Self {
-        let mut board = RectangularBoard::new(3, 3);
-        for l in s.lines() {
-            let numbers: Vec<_> = l.split(' ').map(|x| x.parse::<usize>().unwrap()).collect();
-            assert!(numbers.len() == 3);
-            board.board.prefill_value(&Coord::new(numbers[0] - 1, numbers[1] - 1),
-                                      numbers[2] - 1);
+    /// Create an X-Sudoku puzzle, where the values on both main diagonals
+    /// are different as well.
+    pub fn new_diagonal(block_width: usize, block_height: usize) -> Self {
+        let board = RectangularBoard::new(block_width, block_height);
+        let group_size = board.group_size();
+        let cells = board.board.cells;
+        let mut groups = board.board.groups.to_vec();
+        groups.push(Group::new((0..group_size).map(|i| Coord::new(i, i)).collect()));
+        groups.push(Group::new((0..group_size).map(|i| Coord::new(group_size - 1 - i, i)).collect()));
+        RectangularBoard {
+            block_width,
+            block_height,
+            variant: Variant::Diagonal,
+            chess_rules: Vec::new(),
+            board: Board::new(
+                cells,
+                groups,
+            ),
+        }
+    }
+
+    /// The symbol marking `coord` when it is empty: `\` and `/` on the
+    /// diagonals of a diagonal board, `*` where they cross, and `None`
+    /// for other cells.
+    fn diagonal_marker(&self, coord: &Coord) -> Option<&'static str> {
+        if self.variant != Variant::Diagonal {
+            return None;
+        }
+        let last = self.group_size() - 1;
+        match (coord.x == coord.y, coord.x + coord.y == last) {
+            (true, true) => Some("*"),
+            (true, false) => Some("\\"),
+            (false, true) => Some("/"),
+            (false, false) => None,
         }
-        board
     }
 
     pub fn pretty_print(&self) {
@@ -157,6 +344,34 @@ impl RectangularBoard {
         self.board.count_solutions()
     }
 
+    /// Find at most `limit` solutions, see `Board::solve_up_to`.
+    pub fn solve_up_to(&self, limit: usize) -> Vec<Self> {
+        self.solutions().take(limit).collect()
+    }
+
+    /// Returns whether this puzzle has exactly one solution.
+    pub fn has_unique_solution(&self) -> bool {
+        self.count_solutions_up_to(2) == 1
+    }
+
+    /// Iterate lazily over the solutions of this board, see `Board::solutions`.
+    pub fn solutions(&self) -> impl Iterator<Item = RectangularBoard> {
+        let block_width = self.block_width;
+        let block_height = self.block_height;
+        let variant = self.variant;
+        let chess_rules = self.chess_rules.clone();
+        self.board.solutions()
+            .map(move |board| {
+                RectangularBoard {
+                    block_width,
+                    block_height,
+                    variant,
+                    chess_rules: chess_rules.clone(),
+                    board,
+                }
+            })
+    }
+
     pub fn solve(&self) -> Vec<Self> {
         self.board.solve()
             .into_iter()
@@ -164,6 +379,8 @@ impl RectangularBoard {
                 RectangularBoard {
                     block_width: self.block_width,
                     block_height: self.block_height,
+                    variant: self.variant,
+                    chess_rules: self.chess_rules.clone(),
                     board: s,
                 }
             })
@@ -172,8 +389,14 @@ impl RectangularBoard {
 }
 
 impl BoardPrinter for RectangularBoard {
+    /// Print the board in a grid. Once values are found, the givens are
+    /// highlighted in brackets. Empty cells on the diagonals of a diagonal
+    /// board are marked.
     fn pretty_print(&self) -> String {
         let group_size = self.cells.group_size();
+        let highlight_givens = (0..group_size)
+            .flat_map(|y| (0..group_size).map(move |x| Coord::new(x, y)))
+            .any(|c| !self.is_given(&c) && self.get_cell(&c).get_value().is_some());
         let mut result = String::new();
         for yy in 0..(group_size/self.block_height) {
             for y in 0..self.block_height {
@@ -189,13 +412,14 @@ impl BoardPrinter for RectangularBoard {
                             yy * self.block_height + y,
                         );
                         let v = match self.cells.get_cell(&coord).get_value() {
-                            Some(v) => format!("{}", v + 1),
-                            None => String::from(" "),
+                            Some(v) if highlight_givens && self.is_given(&coord) => format!("[{}]", v + 1),
+                            Some(v) => format!(" {} ", v + 1),
+                            None => format!(" {} ", self.diagonal_marker(&coord).unwrap_or(" ")),
                         };
                         if x == 0 {
-                            result.push_str(&format!("| {} ", v));
+                            result.push_str(&format!("|{}", v));
                         } else {
-                            result.push_str(&format!("  {} ", v));
+                            result.push_str(&format!(" {}", v));
                         }
                     }
                 }
@@ -208,16 +432,19 @@ impl BoardPrinter for RectangularBoard {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::get_first)]
 mod tests {
     use super::*;
+    use super::candidates::Candidates;
     use super::cell::Cell;
+    use std::time::Duration;
 
     #[test]
     fn strike_through_forward_works() {
         let mut cell = Cell::new(9);
         for i in 0..8 {
             assert_eq!(cell.get_value(), None);
-            cell.strike_through(i);
+            cell.strike_through(i).unwrap();
         }
         assert_eq!(cell.get_value(), Some(8));
     }
@@ -227,57 +454,777 @@ mod tests {
         let mut cell = Cell::new(9);
         for i in (1..9).rev() {
             assert_eq!(cell.get_value(), None);
-            cell.strike_through(i);
+            cell.strike_through(i).unwrap();
         }
         assert_eq!(cell.get_value(), Some(0));
     }
 
     #[test]
-    #[should_panic]
     fn strike_through_set_value() {
         let mut cell = Cell::new(9);
-        cell.set_value(4);
-        cell.strike_through(4);
+        cell.set_value(4).unwrap();
+        assert_eq!(cell.strike_through(4), Err(Error::ValueAlreadySet { value: 4 }));
+    }
+
+    #[test]
+    fn strike_through_wide_cell_works() {
+        let mut cell = Cell::new(100);
+        assert_eq!(cell.possibilities(), 100);
+        for i in (0..100).filter(|i| *i != 70) {
+            assert_eq!(cell.get_value(), None);
+            cell.strike_through(i).unwrap();
+        }
+        assert_eq!(cell.get_value(), Some(70));
+        assert!(cell.is_possible(70));
+        assert!(!cell.is_possible(7));
+    }
+
+    #[test]
+    fn candidates_iterate_in_order() {
+        let mut candidates = Candidates::empty(130);
+        for value in [129, 0, 64, 63, 65] {
+            candidates.insert(value);
+        }
+        assert_eq!(candidates.iter().collect::<Vec<_>>(), vec![0, 63, 64, 65, 129]);
+        assert_eq!(candidates.len(), 5);
+        candidates.remove(0);
+        assert_eq!(candidates.first(), Some(63));
+        let mut small = Candidates::full(9);
+        assert_eq!(small, Candidates::Small(0x1ff));
+        small.retain_only(8);
+        assert_eq!(small.single(), Some(8));
     }
 
     #[test]
     fn cell_set_value_works() {
         let mut cell = Cell::new(9);
-        cell.set_value(4);
+        cell.set_value(4).unwrap();
         assert_eq!(cell.get_value(), Some(4));
     }
 
+    #[test]
+    fn cell_prefill_value_marks_given() {
+        let mut cell = Cell::new(9);
+        cell.prefill_value(4).unwrap();
+        assert_eq!(cell.get_value(), Some(4));
+        assert!(cell.is_given);
+        assert!(!cell.is_set);
+
+        let mut cell = Cell::new(9);
+        cell.set_value(4).unwrap();
+        assert!(!cell.is_given);
+    }
+
+    #[test]
+    fn givens_survive_solving() {
+        let board = RectangularBoard::from_line(PUZZLE_LINE, 3, 3).unwrap();
+        let solution = board.solutions().next().unwrap();
+        assert!(solution.is_solved());
+        assert_eq!(solution.givens(), board.givens());
+        assert!(solution.is_given(&Coord::new(0, 0)));
+        assert!(!solution.is_given(&Coord::new(5, 0)));
+        assert_eq!(solution.puzzle().to_line(), PUZZLE_LINE);
+        let logical = board.solve_logically().unwrap();
+        assert_eq!(logical.board.givens(), board.givens());
+    }
+
+    #[test]
+    fn pretty_print_highlights_givens() {
+        let mut board = RectangularBoard::new(2, 1);
+        board.board.prefill_value(&Coord::new(0, 0), 0).unwrap();
+        assert_eq!(BoardPrinter::pretty_print(&board), "\
+            +-------+\n\
+            | 1     |\n\
+            +-------+\n\
+            |       |\n\
+            +-------+\n");
+        let solution = board.solutions().next().unwrap();
+        assert_eq!(BoardPrinter::pretty_print(&solution), "\
+            +-------+\n\
+            |[1]  2 |\n\
+            +-------+\n\
+            | 2   1 |\n\
+            +-------+\n");
+    }
+
+    #[test]
+    fn cell_prefill_out_of_range() {
+        let mut cell = Cell::new(9);
+        assert_eq!(cell.prefill_value(9), Err(Error::ValueOutOfRange { value: 9, group_size: 9 }));
+    }
+
+    #[test]
+    fn from_string_works() {
+        let board = RectangularBoard::from_string("1 1 5\n\n9 9 7\n").unwrap();
+        assert_eq!(board.get_cell(&Coord::new(0, 0)).get_value(), Some(4));
+        assert_eq!(board.get_cell(&Coord::new(8, 8)).get_value(), Some(6));
+    }
+
+    #[test]
+    fn from_string_reports_parse_errors() {
+        assert_eq!(
+            RectangularBoard::from_string("1 1 5\n2 x 3").err(),
+            Some(Error::Parse { line: 2, column: 3, message: String::from("expected a positive number, found 'x'") })
+        );
+        assert!(matches!(
+            RectangularBoard::from_string("1 1"),
+            Err(Error::Parse { line: 1, column: 4, .. })
+        ));
+        assert!(matches!(
+            RectangularBoard::from_string("1 1 5 6"),
+            Err(Error::Parse { line: 1, column: 7, .. })
+        ));
+    }
+
+    #[test]
+    fn from_string_reports_out_of_range() {
+        assert_eq!(
+            RectangularBoard::from_string("10 1 5").err(),
+            Some(Error::CoordOutOfRange { coord: Coord::new(9, 0), group_size: 9 })
+        );
+        assert_eq!(
+            RectangularBoard::from_string("1 1 10").err(),
+            Some(Error::ValueOutOfRange { value: 9, group_size: 9 })
+        );
+    }
+
+    #[test]
+    fn from_string_reports_conflicting_givens() {
+        assert_eq!(
+            RectangularBoard::from_string("1 1 5\n1 9 5").err(),
+            Some(Error::ConflictingGivens { coord: Coord::new(0, 8), value: 4 })
+        );
+    }
+
+    const PUZZLE_LINE: &str =
+        "58492...3.7.16.54..6.5.4....29.7.65.1..2.5...745...9....78..31...371..856.8.52.97";
+
+    #[test]
+    fn from_line_matches_from_string() {
+        let from_line = RectangularBoard::from_line(PUZZLE_LINE, 3, 3).unwrap();
+        let from_string = RectangularBoard::from_string(include_str!("../puzzle.txt")).unwrap();
+        assert_eq!(from_line.to_line(), from_string.to_line());
+        assert_eq!(from_line.to_line(), PUZZLE_LINE);
+    }
+
+    #[test]
+    fn from_line_accepts_zero_as_empty() {
+        let board = RectangularBoard::from_line(&PUZZLE_LINE.replace('.', "0"), 3, 3).unwrap();
+        assert_eq!(board.to_line(), PUZZLE_LINE);
+    }
+
+    #[test]
+    fn from_line_reports_errors() {
+        assert!(matches!(
+            RectangularBoard::from_line(&PUZZLE_LINE[1..], 3, 3),
+            Err(Error::Parse { line: 1, column: 81, .. })
+        ));
+        assert!(matches!(
+            RectangularBoard::from_line(&PUZZLE_LINE.replacen('.', "x", 1), 3, 3),
+            Err(Error::Parse { line: 1, column: 6, .. })
+        ));
+        assert!(matches!(RectangularBoard::from_line("1", 0, 1), Err(Error::Geometry { .. })));
+        assert!(matches!(RectangularBoard::from_line("1", 1, 0), Err(Error::Geometry { .. })));
+    }
+
+    #[test]
+    fn line_round_trip_uses_letters_past_nine() {
+        let mut board = RectangularBoard::new(4, 4);
+        board.board.prefill_value(&Coord::new(0, 0), 15).unwrap();
+        board.board.prefill_value(&Coord::new(1, 0), 9).unwrap();
+        board.board.prefill_value(&Coord::new(2, 0), 8).unwrap();
+        let line = board.to_line();
+        assert_eq!(line.len(), 256);
+        assert!(line.starts_with("GA9."));
+        assert_eq!(RectangularBoard::from_line(&line, 4, 4).unwrap().to_line(), line);
+    }
+
+    #[test]
+    fn line_round_trip_uses_tokens_past_thirty_five() {
+        let mut board = RectangularBoard::new(6, 6);
+        board.board.prefill_value(&Coord::new(0, 0), 35).unwrap();
+        board.board.prefill_value(&Coord::new(2, 0), 0).unwrap();
+        let line = board.to_line();
+        assert!(line.starts_with("36 . 1 . "));
+        assert_eq!(RectangularBoard::from_line(&line, 6, 6).unwrap().to_line(), line);
+    }
+
+    #[test]
+    fn grid_round_trip() {
+        let board = RectangularBoard::from_line(PUZZLE_LINE, 3, 3).unwrap();
+        let grid = board.to_grid();
+        assert!(grid.starts_with("5 8 4 | 9 2 . | . . 3\n"));
+        assert_eq!(grid.lines().nth(3), Some("------+-------+------"));
+        assert_eq!(RectangularBoard::from_grid(&grid).unwrap().to_line(), PUZZLE_LINE);
+    }
+
+    #[test]
+    fn from_grid_ignores_layout() {
+        let grid = "
+            +---+---+---+
+            |584|92.|..3|
+            |.7.|16.|54.|
+            |.6.|5.4|...|
+            +---+---+---+
+            |.29|.7.|65.|
+            |1..|2.5|...|
+            |745|...|9..|
+            +---+---+---+
+            |..7|8..|31|
+            |..3|71.|.85|
+            |6.8|.52|.97|
+            +---+---+---+";
+        assert!(matches!(
+            RectangularBoard::from_grid(grid),
+            Err(Error::Parse { line: 11, .. })
+        ));
+        let grid = grid.replace("|31|", "|31.|");
+        assert_eq!(RectangularBoard::from_grid(&grid).unwrap().to_line(), PUZZLE_LINE);
+    }
+
+    #[test]
+    fn from_grid_infers_rectangular_blocks() {
+        let mut board = RectangularBoard::new(3, 2);
+        board.board.prefill_value(&Coord::new(0, 0), 0).unwrap();
+        board.board.prefill_value(&Coord::new(5, 5), 5).unwrap();
+        let grid = board.to_grid();
+        assert_eq!(grid.lines().next(), Some("1 . . | . . ."));
+        assert_eq!(grid.lines().nth(2), Some("------+------"));
+        let parsed = RectangularBoard::from_grid(&grid).unwrap();
+        assert_eq!((parsed.block_width, parsed.block_height), (3, 2));
+        assert_eq!(parsed.to_grid(), grid);
+    }
+
+    #[test]
+    fn from_string_reads_size_header() {
+        let board = RectangularBoard::from_string("size 2x3\n1 1 1\n6 6 6\n").unwrap();
+        assert_eq!((board.block_width(), board.block_height()), (2, 3));
+        assert_eq!(board.get_cell(&Coord::new(5, 5)).get_value(), Some(5));
+        assert_eq!(board.to_triples(), "size 2x3\n1 1 1\n6 6 6\n");
+        assert_eq!(
+            RectangularBoard::from_string("size 2x3\n7 1 1").err(),
+            Some(Error::CoordOutOfRange { coord: Coord::new(6, 0), group_size: 6 })
+        );
+    }
+
+    #[test]
+    fn from_string_reads_variant_header() {
+        let board = RectangularBoard::from_string("size 3x3\nvariant nrc\n4 2 4\n").unwrap();
+        assert_eq!(board.variant(), Variant::Nrc);
+        assert_eq!(board.groups.len(), 31);
+        assert_eq!(RectangularBoard::from_string(&board.to_triples()).unwrap().variant(), Variant::Nrc);
+        assert!(matches!(
+            RectangularBoard::from_string("size 2x2\nvariant nrc\n"),
+            Err(Error::Parse { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn from_string_reports_header_errors() {
+        assert!(matches!(
+            RectangularBoard::from_string("size 3by3\n"),
+            Err(Error::Parse { line: 1, column: 6, .. })
+        ));
+        assert!(matches!(
+            RectangularBoard::from_string("variant jigsaw\n"),
+            Err(Error::Parse { line: 1, column: 9, .. })
+        ));
+        assert!(matches!(
+            RectangularBoard::from_string("1 1 1\nsize 2x3\n"),
+            Err(Error::Parse { line: 2, column: 1, .. })
+        ));
+        assert!(matches!(
+            RectangularBoard::from_string("size 9999999999x9999999999\n1 1 1\n"),
+            Err(Error::Parse { line: 2, .. })
+        ));
+        assert!(matches!(
+            RectangularBoard::from_string("size 5000x5000\n1 1 1\n"),
+            Err(Error::Parse { line: 2, .. })
+        ));
+        assert!(matches!(
+            RectangularBoard::from_line("1", 5000, 5000),
+            Err(Error::Geometry { .. })
+        ));
+    }
+
+    #[test]
+    fn to_variant_keeps_values() {
+        let board = RectangularBoard::from_string("1 1 5\n9 9 7\n").unwrap();
+        let nrc = board.to_variant(Variant::Nrc).unwrap();
+        assert_eq!(nrc.variant(), Variant::Nrc);
+        assert_eq!(nrc.to_line(), board.to_line());
+        assert!(RectangularBoard::from_string("2 2 5\n4 4 5\n").unwrap().to_variant(Variant::Nrc).is_err());
+        assert!(RectangularBoard::new(2, 3).to_variant(Variant::Nrc).is_err());
+    }
+
+    #[test]
+    fn solve_batch_preserves_order() {
+        let input = format!("{}\n\n# comment\n{}\n1234\n", PUZZLE_LINE, PUZZLE_LINE.replacen('5', ".", 1));
+        let options = batch::BatchOptions { chunk_size: 2, ..batch::BatchOptions::default() };
+        let mut output = Vec::new();
+        let summary = batch::solve_batch(input.as_bytes(), &mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<Vec<&str>> = output.lines().map(|l| l.split('\t').collect()).collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0][0], PUZZLE_LINE);
+        assert_eq!(lines[0][1], "584927163372168549961534728829473651136295874745681932257849316493716285618352497");
+        assert_eq!(lines[0][2], "1");
+        assert_eq!(lines[1][1], lines[0][1]);
+        assert_eq!(lines[2][0], "1234");
+        assert_eq!(lines[2][1], "-");
+        assert!(lines[2][2].starts_with("error: "));
+        assert_eq!(summary, batch::BatchSummary {
+            puzzles: 3,
+            solved: 2,
+            unique: 2,
+            multiple: 0,
+            unsolvable: 0,
+            errors: 1,
+        });
+    }
+
+    #[test]
+    fn solve_batch_stops_at_limit() {
+        // An empty board has far too many solutions to find them all.
+        let input = format!("{}\n", ".".repeat(81));
+        let options = batch::BatchOptions { solution_limit: 3, ..batch::BatchOptions::default() };
+        let mut output = Vec::new();
+        let summary = batch::solve_batch(input.as_bytes(), &mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let fields: Vec<&str> = output.trim_end().split('\t').collect();
+        assert_eq!(fields[2], "3+");
+        assert_eq!(summary.multiple, 1);
+    }
+
+    #[test]
+    fn solve_batch_tells_limit_from_more() {
+        // The 1s and 2s in the first two cells of rows 1 and 3 can be swapped.
+        let input = "..343412..434321\n";
+        let options = batch::BatchOptions { block_width: 2, block_height: 2, ..batch::BatchOptions::default() };
+        let mut output = Vec::new();
+        let summary = batch::solve_batch(input.as_bytes(), &mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.split('\t').nth(2), Some("2"));
+        assert_eq!(summary.multiple, 1);
+
+        let options = batch::BatchOptions { solution_limit: 1, ..options };
+        let mut output = Vec::new();
+        let summary = batch::solve_batch(input.as_bytes(), &mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.split('\t').nth(2), Some("1+"));
+        assert_eq!(summary.multiple, 1);
+    }
+
+    #[test]
+    fn solutions_are_lazy() {
+        let board = RectangularBoard::new(3, 3);
+        let solutions: Vec<RectangularBoard> = board.solutions().take(2).collect();
+        assert_eq!(solutions.len(), 2);
+        assert!(solutions.iter().all(|s| s.is_solved() && s.is_valid_solution()));
+        assert_ne!(solutions[0].to_line(), solutions[1].to_line());
+    }
+
+    #[test]
+    fn solutions_match_solve() {
+        let board = RectangularBoard::new(2, 2);
+        let mut lazy: Vec<String> = board.solutions().map(|s| s.to_line()).collect();
+        let mut eager: Vec<String> = board.solve().iter().map(|s| s.to_line()).collect();
+        lazy.sort();
+        eager.sort();
+        assert_eq!(lazy.len(), 288);
+        assert_eq!(lazy, eager);
+
+        let puzzle = RectangularBoard::from_line(PUZZLE_LINE, 3, 3).unwrap();
+        assert_eq!(puzzle.solutions().count(), 1);
+    }
+
+    #[test]
+    fn count_solutions_up_to_stops_at_limit() {
+        assert_eq!(RectangularBoard::new(3, 3).count_solutions_up_to(5), 5);
+        assert_eq!(RectangularBoard::new(3, 3).count_solutions_up_to(0), 0);
+        assert_eq!(RectangularBoard::new(2, 2).count_solutions_up_to(1000), 288);
+    }
+
+    #[test]
+    fn has_unique_solution_works() {
+        let puzzle = RectangularBoard::from_line(PUZZLE_LINE, 3, 3).unwrap();
+        assert!(puzzle.has_unique_solution());
+        let sparse = RectangularBoard::from_line(&PUZZLE_LINE.replace('5', "."), 3, 3).unwrap();
+        assert!(!sparse.has_unique_solution());
+        let broken = RectangularBoard::from_string("1 1 1\n2 2 2\n3 3 3\n4 1 4\n5 1 5\n6 1 6\n7 1 7\n8 1 8\n9 1 9\n4 2 9").unwrap();
+        assert_eq!(broken.count_solutions_up_to(2), 0);
+        assert!(!broken.has_unique_solution());
+    }
+
+    #[test]
+    fn peers_are_indexed() {
+        let board = RectangularBoard::new(3, 3);
+        assert_eq!(board.peers_of(&Coord::new(4, 4)).len(), 20);
+        assert_eq!(board.groups_of(&Coord::new(4, 4)).len(), 3);
+        assert!(!board.peers_of(&Coord::new(4, 4)).any(|peer| peer == Coord::new(4, 4)));
+        let nrc = RectangularBoard::new_nrc();
+        assert_eq!(nrc.groups_of(&Coord::new(1, 1)).len(), 4);
+        assert_eq!(nrc.peers_of(&Coord::new(1, 1)).len(), 23);
+    }
+
+    #[test]
+    fn solve_empty_16_by_16() {
+        let board = RectangularBoard::new(4, 4);
+        let solution = board.solutions().next().unwrap();
+        assert!(solution.is_solved());
+        assert!(solution.is_valid_solution());
+    }
+
+    #[test]
+    fn hidden_singles_solve_without_guessing() {
+        for line in [
+            "..2.3...8.....8....31.2.....6..5.27..1.....5.2.4.6..31....8.6.5.......13..531.4..",
+            "000000010400000000020000000000050407008000300001090000300400200050100000000806000",
+        ] {
+            let mut board = RectangularBoard::from_line(line, 3, 3).unwrap();
+            board.board.discover_new_values().unwrap();
+            assert!(board.is_solved());
+            assert!(board.is_valid_solution());
+        }
+    }
+
+    #[test]
+    fn hidden_singles_report_missing_place() {
+        // The 2s in the first and third row, and the 1 in the corner, leave no place for a 2 in the last column.
+        let mut board = RectangularBoard::from_string("size 2x2\n2 3 1\n4 4 1\n1 3 2\n3 1 2\n").unwrap();
+        assert_eq!(board.board.discover_new_values(), Err(Error::NoPlaceForValue { group: 3, value: 1 }));
+    }
+
+    /// Solve `line` logically, checking every step against the unique solution.
+    fn assert_logical_steps_are_sound(board: &RectangularBoard) -> LogicalSolution {
+        let solution = board.solutions().next().unwrap();
+        let logical = board.solve_logically().unwrap();
+        for step in &logical.steps {
+            for (coord, value) in &step.placements {
+                assert_eq!(solution.get_cell(coord).get_value(), Some(*value), "{:?}", step);
+            }
+            for (coord, value) in &step.eliminations {
+                assert_ne!(solution.get_cell(coord).get_value(), Some(*value), "{:?}", step);
+            }
+        }
+        logical
+    }
+
+    #[test]
+    fn solve_logically_with_x_wing() {
+        let board = RectangularBoard::from_line(
+            "1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5", 3, 3).unwrap();
+        let logical = assert_logical_steps_are_sound(&board);
+        assert!(logical.is_solved());
+        assert_eq!(logical.hardest_technique(), Some(Technique::XWing));
+    }
+
+    #[test]
+    fn solve_logically_with_xy_wing() {
+        let board = RectangularBoard::from_line(
+            "9..24.....5.69.231.2..5..9..9.7..32...29356.7.7...29...69.2..7351..79.622.7.86..9", 3, 3).unwrap();
+        let logical = assert_logical_steps_are_sound(&board);
+        assert!(logical.is_solved());
+        assert_eq!(logical.hardest_technique(), Some(Technique::XYWing));
+    }
+
+    #[test]
+    fn solve_logically_stops_when_stuck() {
+        let board = RectangularBoard::from_line(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..", 3, 3).unwrap();
+        let logical = assert_logical_steps_are_sound(&board);
+        assert!(!logical.is_solved());
+    }
+
+    #[test]
+    fn solve_nrc_puzzle_logically() {
+        let board = RectangularBoard::from_string("variant nrc\n4 2 4\n7 2 5\n4 3 2\n9 3 8\n2 4 9\n3 4 1\n\
+            4 4 5\n7 4 7\n2 5 2\n3 5 7\n4 5 8\n5 5 6\n1 6 3\n4 6 1\n5 6 4\n7 6 9\n6 7 3\n1 8 7\n3 8 3\n\
+            5 8 5\n7 8 8\n").unwrap();
+        assert_logical_steps_are_sound(&board);
+    }
+
+    #[test]
+    fn x_wing_in_crafted_position() {
+        let mut board = RectangularBoard::new(3, 3);
+        for y in [1, 4] {
+            for x in (0..9).filter(|x| *x != 2 && *x != 6) {
+                board.board.strike_through(&Coord::new(x, y), 0).unwrap();
+            }
+        }
+        let step = Technique::XWing.find(&board).unwrap();
+        assert!(step.placements.is_empty());
+        assert_eq!(step.eliminations.len(), 14);
+        assert!(step.eliminations.iter().all(|(c, v)| *v == 0 && (c.x == 2 || c.x == 6) && c.y != 1 && c.y != 4));
+    }
+
+    #[test]
+    fn steps_record_cells_and_groups() {
+        let board = RectangularBoard::from_line(
+            "1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5", 3, 3).unwrap();
+        let logical = board.solve_logically().unwrap();
+        let x_wing = logical.steps.iter().find(|s| s.technique == Technique::XWing).unwrap();
+        assert_eq!(x_wing.cells.len(), 4);
+        assert_eq!(x_wing.groups.len(), 4);
+        assert_eq!(x_wing.describe(&board),
+                   "X-Wing in row 2, row 6, column 4, column 8 on r2c4, r2c8, r6c4, r6c8: \
+                    removes 7 from r1c4, r5c4, r8c4, r9c4, r4c8, r5c8, r8c8, r9c8");
+        let single = logical.steps.iter().find(|s| s.technique == Technique::HiddenSingle).unwrap();
+        assert_eq!(single.describe(&board), "Hidden single in row 8: r8c2 = 7");
+        assert_eq!(single.to_json(&board),
+                   "{\"technique\":\"Hidden single\",\"description\":\"Hidden single in row 8: r8c2 = 7\",\
+                    \"cells\":[{\"row\":8,\"column\":2}],\"groups\":[{\"index\":16,\"name\":\"row 8\"}],\
+                    \"placements\":[{\"row\":8,\"column\":2,\"value\":7}],\"eliminations\":[]}");
+        let explanation = logical.explain();
+        assert_eq!(explanation.lines().count(), logical.steps.len());
+        assert!(explanation.starts_with("1. Pointing pair in block at r4c4, column 4"));
+        assert!(logical.to_json().starts_with("{\"solved\":true,\"steps\":[{\"technique\":\"Pointing pair\""));
+    }
+
+    #[test]
+    fn hint_returns_the_easiest_step() {
+        let mut board = RectangularBoard::from_line(
+            "1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5", 3, 3).unwrap();
+        let hint = board.hint().unwrap().unwrap();
+        assert_eq!(hint.technique, Technique::PointingPair);
+        assert_eq!(hint.eliminations, vec![(Coord::new(3, 8), 8)]);
+        assert!(board.get_cell(&Coord::new(3, 8)).is_possible(8));
+        // Once the player applies it, the next hint moves on.
+        board.board.strike_through(&Coord::new(3, 8), 8).unwrap();
+        assert_eq!(board.hint().unwrap().unwrap().technique, Technique::XWing);
+    }
+
+    #[test]
+    fn hint_finds_naked_single_left_by_the_player() {
+        let mut board = RectangularBoard::new(3, 3);
+        for value in 1..9 {
+            board.board.strike_through(&Coord::new(0, 0), value).unwrap();
+        }
+        let hint = board.hint().unwrap().unwrap();
+        assert_eq!(hint.technique, Technique::NakedSingle);
+        assert_eq!(hint.placements, vec![(Coord::new(0, 0), 0)]);
+        let logical = board.solve_logically().unwrap();
+        assert_eq!(logical.steps[0].technique, Technique::NakedSingle);
+    }
+
+    #[test]
+    fn hint_on_solved_board() {
+        let board = RectangularBoard::from_line(
+            "584927163372168549961534728829473651136295874745681932257849316493716285618352497", 3, 3).unwrap();
+        assert_eq!(board.hint(), Ok(None));
+    }
+
+    #[test]
+    fn grade_by_hardest_technique() {
+        let board = RectangularBoard::from_line(PUZZLE_LINE, 3, 3).unwrap();
+        let grade = board.grade().unwrap();
+        assert_eq!(grade.hardest_technique, Some(Technique::HiddenSingle));
+        assert_eq!(grade.rating, 1.5);
+        let board = RectangularBoard::from_line(
+            "1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5", 3, 3).unwrap();
+        let grade = board.grade().unwrap();
+        assert_eq!(grade.hardest_technique.map(|t| t.family()), Some(TechniqueFamily::Fish));
+        assert_eq!(grade.rating, 3.2);
+        assert!(!grade.needs_guessing());
+        assert_eq!(grade.search, SearchStatistics::default());
+    }
+
+    #[test]
+    fn grade_puzzle_needing_guesses() {
+        let board = RectangularBoard::from_line(
+            "8..........36......7..9.2...5...7.......457.....1...3...1....68..85...1..9....4..", 3, 3).unwrap();
+        let grade = board.grade().unwrap();
+        assert!(grade.needs_guessing());
+        assert!(grade.rating >= 7.0 && grade.rating <= 11.9);
+        assert_eq!(grade.search.solutions, 1);
+        assert!(grade.search.branches > 1);
+        assert!(grade.search.depth > 1);
+    }
+
+    #[test]
+    fn technique_ratings_follow_their_order() {
+        for pair in Technique::ALL.windows(2) {
+            assert!(pair[0].rating() < pair[1].rating());
+        }
+    }
+
+    #[test]
+    fn generate_unique_minimal_puzzles() {
+        for template in [RectangularBoard::new(2, 2), RectangularBoard::new(3, 2), RectangularBoard::new(3, 3),
+                         RectangularBoard::new_nrc()] {
+            let puzzle = template.generate(42).unwrap();
+            assert!(puzzle.has_unique_solution());
+            assert_eq!(puzzle.variant(), template.variant());
+            assert_eq!(puzzle.to_line(), template.generate(42).unwrap().to_line());
+            let line = puzzle.to_line();
+            for (index, _) in line.char_indices().filter(|(_, c)| *c != '.') {
+                let mut reduced = line.clone();
+                reduced.replace_range(index..index + 1, ".");
+                let reduced = RectangularBoard::from_line(&reduced, puzzle.block_width(), puzzle.block_height())
+                    .unwrap()
+                    .to_variant(puzzle.variant())
+                    .unwrap();
+                assert!(!reduced.has_unique_solution());
+            }
+        }
+    }
+
+    #[test]
+    fn generate_depends_on_seed() {
+        let template = RectangularBoard::new(3, 3);
+        assert_ne!(template.generate(1).unwrap().to_line(), template.generate(2).unwrap().to_line());
+    }
+
+    #[test]
+    fn generate_keeps_givens() {
+        let mut template = RectangularBoard::new(3, 3);
+        for i in 0..9 {
+            template.board.prefill_value(&Coord::new(i, i), i).unwrap();
+        }
+        let puzzle = template.generate(7).unwrap();
+        for (t, p) in template.to_line().chars().zip(puzzle.to_line().chars()) {
+            assert!(t == '.' || t == p);
+        }
+        assert!(puzzle.has_unique_solution());
+    }
+
+    #[test]
+    fn generate_symmetric_puzzles() {
+        let template = RectangularBoard::new(3, 3);
+        for symmetry in Symmetry::ALL {
+            let puzzle = template.generate_symmetric(11, symmetry).unwrap();
+            assert!(puzzle.has_unique_solution());
+            if symmetry != Symmetry::None {
+                assert!(puzzle.symmetries().contains(&symmetry), "{:?}", symmetry);
+            }
+        }
+        let puzzle = RectangularBoard::new_nrc().generate_symmetric(11, Symmetry::Rotational90).unwrap();
+        assert!(puzzle.has_unique_solution());
+        assert!(puzzle.symmetries().contains(&Symmetry::Rotational90));
+    }
+
+    #[test]
+    fn symmetry_orbits() {
+        assert_eq!(Symmetry::Rotational90.orbit(&Coord::new(0, 0), 9),
+                   vec![Coord::new(0, 0), Coord::new(8, 0), Coord::new(8, 8), Coord::new(0, 8)]);
+        assert_eq!(Symmetry::Rotational180.orbit(&Coord::new(4, 4), 9), vec![Coord::new(4, 4)]);
+        assert_eq!(Symmetry::Diagonal.orbit(&Coord::new(1, 2), 9), vec![Coord::new(1, 2), Coord::new(2, 1)]);
+        assert_eq!(Symmetry::from_name("Horizontal"), Some(Symmetry::Horizontal));
+    }
+
+    #[test]
+    fn detect_symmetries() {
+        let board = RectangularBoard::from_line(
+            "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79", 3, 3).unwrap();
+        assert_eq!(board.symmetries(), vec![Symmetry::Rotational180]);
+        assert_eq!(RectangularBoard::new(3, 3).symmetries().len(), 5);
+        assert!(RectangularBoard::from_line(PUZZLE_LINE, 3, 3).unwrap().symmetries().is_empty());
+    }
+
+    #[test]
+    fn generate_with_difficulty_matches_target() {
+        let template = RectangularBoard::new(3, 3);
+        let target = DifficultyTarget::from_name("medium").unwrap();
+        let mut reports = 0;
+        let puzzles = template.generate_with_difficulty(5, &target, 2, Duration::from_secs(600), |progress| {
+            reports += 1;
+            assert_eq!(progress.attempts, reports);
+        });
+        assert_eq!(puzzles.len(), 2);
+        for (puzzle, grade) in &puzzles {
+            assert!(grade.rating >= 2.6 && grade.rating <= 3.4);
+            assert_eq!(puzzle.grade().as_ref(), Ok(grade));
+            assert!(puzzle.has_unique_solution());
+        }
+    }
+
+    #[test]
+    fn generate_requiring_technique() {
+        let template = RectangularBoard::new(3, 3);
+        let target = DifficultyTarget::requiring(Technique::PointingPair);
+        let puzzles = template.generate_with_difficulty(5, &target, 1, Duration::from_secs(600), |_| ());
+        assert!(puzzles[0].1.techniques.contains(&Technique::PointingPair));
+        assert!(template.generate_with_difficulty(5, &target, 1, Duration::ZERO, |_| ()).is_empty());
+    }
+
+    #[test]
+    fn technique_from_name() {
+        assert_eq!(Technique::from_name("x-wing"), Some(Technique::XWing));
+        assert_eq!(Technique::from_name("Box/Line reduction"), Some(Technique::BoxLineReduction));
+        assert_eq!(Technique::from_name("wing"), None);
+    }
+
+    #[test]
+    fn reduce_to_minimal_puzzle() {
+        let board = RectangularBoard::from_line(PUZZLE_LINE, 3, 3).unwrap();
+        assert!(!board.is_minimal());
+        let minimal = board.reduce_to_minimal().unwrap();
+        assert!(minimal.is_minimal());
+        assert!(minimal.givens().len() < board.givens().len());
+        assert!(minimal.givens().iter().all(|g| board.givens().contains(g)));
+        assert_eq!(minimal.solutions().next().unwrap().to_line(), board.solutions().next().unwrap().to_line());
+        assert_eq!(minimal.reduce_to_minimal().unwrap().to_line(), minimal.to_line());
+    }
+
+    #[test]
+    fn reduce_to_minimal_requires_unique_solution() {
+        let board = RectangularBoard::new(2, 2);
+        assert!(!board.is_minimal());
+        assert!(board.reduce_to_minimal().is_none());
+        let puzzle = RectangularBoard::new(3, 2).generate(3).unwrap();
+        assert!(puzzle.is_minimal());
+    }
+
+    #[test]
+    fn group_names() {
+        let board = RectangularBoard::new_nrc();
+        assert_eq!(board.group_name(0), "column 1");
+        assert_eq!(board.group_name(10), "row 2");
+        assert_eq!(board.group_name(22), "block at r4c4");
+        assert_eq!(board.group_name(27), "block at r2c2");
+    }
+
+    #[test]
+    fn set_value_reports_conflict() {
+        let mut board = RectangularBoard::new(3, 3);
+        board.board.set_value(&Coord::new(0, 0), 3).unwrap();
+        assert_eq!(
+            board.board.set_value(&Coord::new(4, 0), 3),
+            Err(Error::Conflict { coord: Coord::new(0, 0), value: 3 })
+        );
+    }
+
     #[test]
     fn test_board() {
         let mut board = RectangularBoard::new(3, 3);
-        board.board.set_value(&Coord::new(3, 3), 3);
+        board.board.set_value(&Coord::new(3, 3), 3).unwrap();
         assert_eq!(board.get_cell(&Coord::new(3, 3)).get_value(), Some(3));
     }
 
     #[test]
     fn solve_nrc_puzzle() {
         let mut board = RectangularBoard::new_nrc();
-        board.board.prefill_value(&Coord::new(3, 1), 3);
-        board.board.prefill_value(&Coord::new(6, 1), 4);
-        board.board.prefill_value(&Coord::new(3, 2), 1);
-        board.board.prefill_value(&Coord::new(8, 2), 7);
-        board.board.prefill_value(&Coord::new(1, 3), 8);
-        board.board.prefill_value(&Coord::new(2, 3), 0);
-        board.board.prefill_value(&Coord::new(3, 3), 4);
-        board.board.prefill_value(&Coord::new(6, 3), 6);
-        board.board.prefill_value(&Coord::new(1, 4), 1);
-        board.board.prefill_value(&Coord::new(2, 4), 6);
-        board.board.prefill_value(&Coord::new(3, 4), 7);
-        board.board.prefill_value(&Coord::new(4, 4), 5);
-        board.board.prefill_value(&Coord::new(0, 5), 2);
-        board.board.prefill_value(&Coord::new(3, 5), 0);
-        board.board.prefill_value(&Coord::new(4, 5), 3);
-        board.board.prefill_value(&Coord::new(6, 5), 8);
-        board.board.prefill_value(&Coord::new(5, 6), 2);
-        board.board.prefill_value(&Coord::new(0, 7), 6);
-        board.board.prefill_value(&Coord::new(2, 7), 2);
-        board.board.prefill_value(&Coord::new(4, 7), 4);
-        board.board.prefill_value(&Coord::new(6, 7), 7);
+        board.board.prefill_value(&Coord::new(3, 1), 3).unwrap();
+        board.board.prefill_value(&Coord::new(6, 1), 4).unwrap();
+        board.board.prefill_value(&Coord::new(3, 2), 1).unwrap();
+        board.board.prefill_value(&Coord::new(8, 2), 7).unwrap();
+        board.board.prefill_value(&Coord::new(1, 3), 8).unwrap();
+        board.board.prefill_value(&Coord::new(2, 3), 0).unwrap();
+        board.board.prefill_value(&Coord::new(3, 3), 4).unwrap();
+        board.board.prefill_value(&Coord::new(6, 3), 6).unwrap();
+        board.board.prefill_value(&Coord::new(1, 4), 1).unwrap();
+        board.board.prefill_value(&Coord::new(2, 4), 6).unwrap();
+        board.board.prefill_value(&Coord::new(3, 4), 7).unwrap();
+        board.board.prefill_value(&Coord::new(4, 4), 5).unwrap();
+        board.board.prefill_value(&Coord::new(0, 5), 2).unwrap();
+        board.board.prefill_value(&Coord::new(3, 5), 0).unwrap();
+        board.board.prefill_value(&Coord::new(4, 5), 3).unwrap();
+        board.board.prefill_value(&Coord::new(6, 5), 8).unwrap();
+        board.board.prefill_value(&Coord::new(5, 6), 2).unwrap();
+        board.board.prefill_value(&Coord::new(0, 7), 6).unwrap();
+        board.board.prefill_value(&Coord::new(2, 7), 2).unwrap();
+        board.board.prefill_value(&Coord::new(4, 7), 4).unwrap();
+        board.board.prefill_value(&Coord::new(6, 7), 7).unwrap();
 
         assert_eq!(board.is_solved(), false);
 
@@ -288,50 +1235,653 @@ mod tests {
         assert_eq!(solutions.get(0).map(|it| it.is_solved()), Some(true));
     }
 
+    const DIAGONAL_PUZZLE: &str = "\
+        \\ . . | . 1 9 | . 6 /\n\
+        . \\ . | . 4 . | . 7 .\n\
+        . . 6 | . . . | / 3 .\n\
+        ------+-------+------\n\
+        . . . | \\ . 2 | . . .\n\
+        . . 8 | . * . | . . .\n\
+        7 . 2 | 4 9 \\ | . . .\n\
+        ------+-------+------\n\
+        . . / | . . . | \\ . .\n\
+        . / . | . . . | . 2 5\n\
+        3 . . | . . 5 | 8 . \\\n";
+
+    #[test]
+    fn diagonal_board_has_diagonal_groups() {
+        let board = RectangularBoard::new_diagonal(3, 3);
+        assert_eq!(board.variant(), Variant::Diagonal);
+        assert_eq!(board.groups.len(), 29);
+        assert_eq!(board.peers_of(&Coord::new(0, 0)).len(), 26);
+        assert_eq!(board.peers_of(&Coord::new(4, 4)).len(), 32);
+        assert_eq!(board.peers_of(&Coord::new(1, 0)).len(), 20);
+        assert_eq!(board.group_name(27), "main diagonal");
+        assert_eq!(board.group_name(28), "anti-diagonal");
+        assert_eq!(RectangularBoard::with_variant(3, 2, Variant::Diagonal).unwrap().groups.len(), 20);
+    }
+
+    #[test]
+    fn solve_diagonal_puzzle() {
+        let board = RectangularBoard::from_grid(DIAGONAL_PUZZLE).unwrap();
+        assert_eq!(board.variant(), Variant::Diagonal);
+        let solutions: Vec<RectangularBoard> = board.solutions().take(2).collect();
+        assert_eq!(solutions.len(), 1);
+        let solution = &solutions[0];
+        assert!(solution.is_valid_solution());
+        assert_eq!(solution.to_line(),
+                   "473219568895346172216578934649832751138657249752491683581724396967183425324965817");
+        assert!(!board.to_variant(Variant::Standard).unwrap().has_unique_solution());
+        assert_logical_steps_are_sound(&board);
+    }
+
+    #[test]
+    fn solve_6_by_6_diagonal_puzzle() {
+        let board = RectangularBoard::from_line("..4.................531.6...4.......", 3, 2).unwrap()
+            .to_variant(Variant::Diagonal)
+            .unwrap();
+        assert!(board.has_unique_solution());
+        let solution = board.solutions().next().unwrap();
+        let group_size = solution.group_size();
+        for diagonal in [|i: usize, _: usize| Coord::new(i, i), |i: usize, n: usize| Coord::new(n - 1 - i, i)] {
+            let mut values: Vec<usize> = (0..group_size)
+                .map(|i| solution.get_cell(&diagonal(i, group_size)).get_value().unwrap())
+                .collect();
+            values.sort();
+            assert_eq!(values, (0..group_size).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn diagonal_grid_round_trip() {
+        let board = RectangularBoard::from_grid(DIAGONAL_PUZZLE).unwrap();
+        let grid = board.to_grid();
+        assert_eq!(grid, DIAGONAL_PUZZLE);
+        let triples = board.to_triples();
+        assert!(triples.starts_with("variant diagonal\n"));
+        assert_eq!(RectangularBoard::from_string(&triples).unwrap().to_grid(), grid);
+        // Without markers, a grid is read as a standard board.
+        let plain = RectangularBoard::from_grid(&grid.replace(['\\', '/', '*'], ".")).unwrap();
+        assert_eq!(plain.variant(), Variant::Standard);
+        assert_eq!(plain.to_line(), board.to_line());
+    }
+
+    #[test]
+    fn pretty_print_marks_diagonals() {
+        let mut board = RectangularBoard::new_diagonal(3, 1);
+        board.board.prefill_value(&Coord::new(2, 0), 0).unwrap();
+        assert_eq!(BoardPrinter::pretty_print(&board), "\
+            +-----------+\n\
+            | \\       1 |\n\
+            +-----------+\n\
+            |     *     |\n\
+            +-----------+\n\
+            | /       \\ |\n\
+            +-----------+\n");
+    }
+
+    const JIGSAW_REGIONS: &str = "\
+        AAABBBCCC\n\
+        AAABBBBCC\n\
+        ADABECCCC\n\
+        DDABEFFFF\n\
+        DDEEEEEFF\n\
+        DDDDEEFFF\n\
+        GGGHHIIII\n\
+        GGGHHIHII\n\
+        GGGHHHHII\n";
+
+    const JIGSAW_PUZZLE: &str =
+        "................6....2....3..............9.3...283.594.2..9...7.19...4.5.....762.";
+
+    #[test]
+    fn jigsaw_regions_are_validated() {
+        let board = JigsawBoard::new(JIGSAW_REGIONS).unwrap();
+        assert_eq!(board.groups.len(), 27);
+        assert_eq!(board.region_name(&Coord::new(1, 2)), 'D');
+        assert_eq!(board.group_name(21), "block at r3c2");
+        assert_eq!(board.peers_of(&Coord::new(1, 2)).len(), 21);
+        assert_eq!(
+            JigsawBoard::new("AAA\nABB\nBBC\n").err(),
+            Some(Error::Geometry { message: String::from("region 'A' has 4 cells, expected 3") })
+        );
+        assert_eq!(
+            JigsawBoard::new("ABA\nBBA\nCCC\n").err(),
+            Some(Error::Geometry { message: String::from("region 'A' is not connected") })
+        );
+        assert_eq!(
+            JigsawBoard::new("AAA\nBBB\nBBB\n").err(),
+            Some(Error::Geometry { message: String::from("expected 3 regions, found 2") })
+        );
+        assert_eq!(
+            JigsawBoard::new("AAA\nBBBB\nCCC\n").err(),
+            Some(Error::Parse { line: 2, column: 4, message: String::from("expected 3 regions, found 4") })
+        );
+        assert!(matches!(JigsawBoard::new(&"A\n".repeat(MAX_GROUP_SIZE + 1)), Err(Error::Geometry { .. })));
+    }
+
+    #[test]
+    fn solve_jigsaw_puzzle() {
+        let board = JigsawBoard::from_line(JIGSAW_REGIONS, JIGSAW_PUZZLE).unwrap();
+        assert!(board.has_unique_solution());
+        let solution = board.solutions().next().unwrap();
+        assert!(solution.is_valid_solution());
+        assert_eq!(solution.to_line(),
+                   "263478951487915362951264873395642718148759236672831594826593147719326485534187629");
+        assert_eq!(solution.to_region_map(), JIGSAW_REGIONS);
+        assert!(!RectangularBoard::from_line(JIGSAW_PUZZLE, 3, 3).unwrap().has_unique_solution());
+        assert!(board.solve_logically().unwrap().is_solved());
+    }
+
+    #[test]
+    fn jigsaw_round_trip() {
+        let board = JigsawBoard::from_line(JIGSAW_REGIONS, JIGSAW_PUZZLE).unwrap();
+        assert_eq!(board.to_line(), JIGSAW_PUZZLE);
+        let text = format!("{}\n{}\n", board.to_region_map(), board.to_line());
+        let parsed = JigsawBoard::from_string(&text).unwrap();
+        assert_eq!(parsed.to_region_map(), JIGSAW_REGIONS);
+        assert_eq!(parsed.to_line(), JIGSAW_PUZZLE);
+        assert_eq!(
+            JigsawBoard::from_string("AB\nAB\n\n1x..\n").err(),
+            Some(Error::Parse { line: 4, column: 2, message: String::from("'x' is not a value between 1 and 2") })
+        );
+    }
+
+    #[test]
+    fn jigsaw_from_string_accepts_crlf() {
+        let board = JigsawBoard::from_line(JIGSAW_REGIONS, JIGSAW_PUZZLE).unwrap();
+        let text = format!("{}\n{}\n", board.to_region_map(), board.to_line()).replace('\n', "\r\n");
+        let parsed = JigsawBoard::from_string(&text).unwrap();
+        assert_eq!(parsed.to_region_map(), JIGSAW_REGIONS);
+        assert_eq!(parsed.to_line(), JIGSAW_PUZZLE);
+        assert_eq!(
+            JigsawBoard::from_string("AB\r\nAB\r\n\r\n1x..\r\n").err(),
+            Some(Error::Parse { line: 4, column: 2, message: String::from("'x' is not a value between 1 and 2") })
+        );
+    }
+
+    #[test]
+    fn pretty_print_draws_jigsaw_regions() {
+        let board = JigsawBoard::from_line("AAAB\nCABB\nCCDB\nCDDD\n", "1.........3.....").unwrap();
+        assert_eq!(BoardPrinter::pretty_print(&board), "\
+            +---+---+---+---+\n\
+            | 1         |   |\n\
+            +---+   +---+   +\n\
+            |   |   |       |\n\
+            +   +---+---+   +\n\
+            |       | 3 |   |\n\
+            +   +---+   +---+\n\
+            |   |           |\n\
+            +---+---+---+---+\n");
+        let solution = board.solutions().next().unwrap();
+        assert!(BoardPrinter::pretty_print(&solution).starts_with("+---+---+---+---+\n|[1]  3   2 | 4 |\n"));
+    }
+
+    const KILLER_PUZZLE: &str = "\
+        cage 14 1,1 2,1 3,1\n\
+        cage 6 4,1 5,1 4,2\n\
+        cage 15 6,1 6,2\n\
+        cage 19 7,1 7,2 8,2 8,1\n\
+        cage 14 9,1 9,2 9,3\n\
+        cage 25 1,2 1,3 1,4 2,2\n\
+        cage 11 3,2 3,3\n\
+        cage 19 5,2 5,3 4,3 5,4\n\
+        cage 5 2,3 2,4\n\
+        cage 17 6,3 6,4 7,4\n\
+        cage 21 7,3 8,3 8,4 8,5\n\
+        cage 17 3,4 4,4\n\
+        cage 13 9,4 9,5 9,6\n\
+        cage 9 1,5 2,5 2,6\n\
+        cage 18 3,5 4,5 4,6\n\
+        cage 13 5,5 6,5 6,6\n\
+        cage 11 7,5 7,6 7,7\n\
+        cage 20 1,6 1,7 2,7\n\
+        cage 10 3,6 3,7 4,7\n\
+        cage 19 5,6 5,7 5,8\n\
+        cage 20 8,6 8,7 8,8 8,9\n\
+        cage 7 6,7 6,8\n\
+        cage 18 9,7 9,8 9,9\n\
+        cage 15 1,8 2,8\n\
+        cage 11 3,8 3,9\n\
+        cage 16 4,8 4,9 5,9\n\
+        cage 17 7,8 7,9 6,9\n\
+        cage 5 1,9 2,9\n";
+
+    fn cage(sum: usize, cells: &[(usize, usize)]) -> Cage {
+        Cage::new(cells.iter().map(|(x, y)| Coord::new(*x, *y)).collect(), sum).unwrap()
+    }
+
+    #[test]
+    fn cage_supported_values() {
+        let mut board = RectangularBoard::new(3, 3);
+        let cage_of_two = cage(4, &[(0, 0), (1, 0)]);
+        let values = |v: &Candidates| v.iter().collect::<Vec<_>>();
+        let supported = cage_of_two.supported_values(&board.cells);
+        assert_eq!(supported.iter().map(values).collect::<Vec<_>>(), vec![vec![0, 2], vec![0, 2]]);
+        assert_eq!(cage_of_two.sum_range(9), (3, 17));
+        board.board.set_value(&Coord::new(4, 0), 0).unwrap();
+        let supported = cage_of_two.supported_values(&board.cells);
+        assert!(supported.iter().all(|v| v.is_empty()));
+        let supported = cage(23, &[(0, 1), (1, 1), (2, 1)]).supported_values(&board.cells);
+        assert_eq!(supported.iter().map(values).collect::<Vec<_>>(), vec![vec![5, 7, 8]; 3]);
+    }
+
+    #[test]
+    fn killer_cages_are_validated() {
+        let board = RectangularBoard::new(3, 3);
+        assert_eq!(
+            KillerBoard::new(&board, vec![cage(3, &[(0, 0), (1, 0)]), cage(3, &[(1, 0), (2, 0)])]).err(),
+            Some(Error::Geometry { message: String::from("r1c2 is in more than one cage") })
+        );
+        assert_eq!(
+            KillerBoard::new(&board, vec![cage(3, &[(0, 0), (2, 0)])]).err(),
+            Some(Error::Geometry { message: String::from("cage at r1c1 is not connected") })
+        );
+        assert_eq!(
+            KillerBoard::new(&board, vec![cage(18, &[(0, 0), (1, 0)])]).err(),
+            Some(Error::Geometry {
+                message: String::from("cage at r1c1 cannot add up to 18, its sum lies between 3 and 17"),
+            })
+        );
+        assert_eq!(
+            KillerBoard::new(&board, vec![cage(3, &[(0, 0), (0, 9)])]).err(),
+            Some(Error::CoordOutOfRange { coord: Coord::new(0, 9), group_size: 9 })
+        );
+        assert_eq!(
+            Cage::new(Vec::new(), 3).err(),
+            Some(Error::Geometry { message: String::from("a cage has no cells") })
+        );
+        assert_eq!(
+            Cage::new(vec![Coord::new(0, 0), Coord::new(1, 0), Coord::new(0, 0)], 6).err(),
+            Some(Error::Geometry { message: String::from("r1c1 is in the same cage twice") })
+        );
+        let row = cage(15, &[(0, 0), (1, 0), (2, 0), (3, 0), (0, 1)]);
+        assert_eq!(
+            Board::with_cages(cell_container::CellContainer::new(4), Vec::new(), vec![row]).err(),
+            Some(Error::Geometry { message: String::from("cage at r1c1 has 5 cells, at most 4 fit") })
+        );
+        let mut board = RectangularBoard::new(3, 3);
+        board.board.prefill_value(&Coord::new(0, 0), 0).unwrap();
+        board.board.prefill_value(&Coord::new(1, 0), 1).unwrap();
+        let killer = KillerBoard::new(&board, vec![cage(3, &[(0, 0), (0, 1)])]).unwrap();
+        assert_eq!(killer.givens(), board.givens());
+        assert_eq!(killer.groups.len(), 28);
+        assert_eq!(killer.group_name(27), "cage at r1c1");
+        assert_eq!(killer.peers_of(&Coord::new(0, 1)).len(), 20);
+        assert_eq!(killer.cage_of(&Coord::new(0, 1)), Some(0));
+        assert_eq!(killer.cage_of(&Coord::new(1, 1)), None);
+    }
+
+    #[test]
+    fn solve_killer_puzzle() {
+        let board = KillerBoard::from_string(KILLER_PUZZLE).unwrap();
+        assert_eq!(board.cages.len(), 28);
+        assert!(board.givens().is_empty());
+        let solutions: Vec<KillerBoard> = board.solutions().take(2).collect();
+        assert_eq!(solutions.len(), 1);
+        let solution = &solutions[0];
+        assert!(solution.is_valid_solution());
+        assert_eq!(solution.to_line(),
+                   "473219568895346172216578934649832751138657249752491683581724396967183425324965817");
+        // Swapping two values keeps the rows, columns and blocks valid, but not the sums.
+        let line = solution.to_line().replace('1', "x").replace('2', "1").replace('x', "2");
+        let swapped = KillerBoard::new(&RectangularBoard::from_line(&line, 3, 3).unwrap(), board.cages.to_vec());
+        assert!(!swapped.unwrap().is_valid_solution());
+    }
+
+    #[test]
+    fn solve_killer_with_redundant_cages() {
+        // Every row adds up to 45 anyway, so these cages leave many solutions.
+        let rows = (0..3).map(|y| cage(45, &(0..9).map(|x| (x, y)).collect::<Vec<_>>())).collect();
+        let board = KillerBoard::new(&RectangularBoard::new(3, 3), rows).unwrap();
+        let solution = board.solutions().next().unwrap();
+        assert!(solution.is_valid_solution());
+    }
+
+    #[test]
+    fn killer_round_trip() {
+        let board = KillerBoard::from_string(KILLER_PUZZLE).unwrap();
+        assert_eq!(board.to_triples(), KILLER_PUZZLE);
+        let text = "size 2x2\ncage 3 1,1 2,1\n1 1 1\n";
+        assert_eq!(KillerBoard::from_string(text).unwrap().to_triples(), text);
+        assert_eq!(
+            RectangularBoard::from_string(text).err(),
+            Some(Error::Parse { line: 2, column: 1, message: String::from("cages need a killer board") })
+        );
+        assert_eq!(
+            KillerBoard::from_string("cage 3 1,1 2;1\n").err(),
+            Some(Error::Parse { line: 1, column: 12, message: String::from("expected a cell like 1,2, found '2;1'") })
+        );
+        assert_eq!(
+            KillerBoard::from_string("cage x 1,1\n").err(),
+            Some(Error::Parse { line: 1, column: 6, message: String::from("expected a positive sum, found 'x'") })
+        );
+        assert_eq!(
+            KillerBoard::from_string("cage 3 1,1 1,1\n").err(),
+            Some(Error::Parse {
+                line: 1,
+                column: 8,
+                message: String::from("unsupported geometry: r1c1 is in the same cage twice"),
+            })
+        );
+    }
+
+    #[test]
+    fn pretty_print_draws_killer_cages() {
+        let mut board = RectangularBoard::new(2, 2);
+        board.board.prefill_value(&Coord::new(0, 0), 0).unwrap();
+        board.board.prefill_value(&Coord::new(3, 3), 0).unwrap();
+        let cages = vec![
+            cage(4, &[(0, 0), (0, 1)]),
+            cage(5, &[(1, 0), (2, 0)]),
+            cage(7, &[(3, 0), (3, 1), (2, 1)]),
+            cage(7, &[(1, 1), (1, 2), (0, 2)]),
+            cage(7, &[(0, 3), (1, 3)]),
+            cage(7, &[(2, 2), (2, 3), (3, 3)]),
+            cage(3, &[(3, 2)]),
+        ];
+        let board = KillerBoard::new(&board, cages).unwrap();
+        assert_eq!(BoardPrinter::pretty_print(&board), "\
+            +4--+5--+---+7--+\n\
+            | 1 |       |   |\n\
+            +   +7--+---+   +\n\
+            |   |   |       |\n\
+            +---+   +7--+3--+\n\
+            |       |   |   |\n\
+            +7--+---+   +---+\n\
+            |       |     1 |\n\
+            +---+---+---+---+\n");
+        assert!(board.has_unique_solution());
+        assert_eq!(board.solutions().next().unwrap().to_line(), "1234341221434321");
+    }
+
+    /// Values increase from the bulb, the first cell, along the tube.
+    struct Thermometer(Vec<Coord>);
+
+    impl Constraint for Thermometer {
+        fn prune(&self, board: &mut Board) -> Result<bool> {
+            let mut pruned = false;
+            for pair in self.0.windows(2) {
+                let (lower, upper) = (&pair[0], &pair[1]);
+                let lowest = board.get_cell(lower).possible_values.first().unwrap_or(0);
+                let highest = board.get_cell(upper).possible_values.iter().last().unwrap_or(0);
+                let excluded: Vec<(Coord, usize)> = board.get_cell(upper).possible_values.iter()
+                    .filter(|v| *v <= lowest)
+                    .map(|v| (upper.clone(), v))
+                    .chain(board.get_cell(lower).possible_values.iter()
+                        .filter(|v| *v >= highest)
+                        .map(|v| (lower.clone(), v)))
+                    .collect();
+                for (coord, value) in excluded {
+                    board.strike_through(&coord, value)?;
+                    pruned = true;
+                }
+            }
+            Ok(pruned)
+        }
+
+        fn is_satisfied(&self, board: &Board) -> bool {
+            self.0.windows(2).all(|pair| {
+                board.get_cell(&pair[0]).get_value() < board.get_cell(&pair[1]).get_value()
+            })
+        }
+    }
+
+    #[test]
+    fn group_constraint_prunes_and_detects_conflicts() {
+        let mut board = RectangularBoard::new(2, 2);
+        board.board.prefill_value(&Coord::new(0, 0), 0).unwrap();
+        let row = board.groups[4].clone();
+        assert_eq!(row.prune(&mut board.board), Ok(true));
+        assert!(!board.get_cell(&Coord::new(3, 0)).is_possible(0));
+        assert_eq!(row.prune(&mut board.board), Ok(false));
+        board.board.cells.get_mut_cell(&Coord::new(2, 0)).possible_values.retain_only(0);
+        assert_eq!(row.prune(&mut board.board), Err(Error::Conflict { coord: Coord::new(2, 0), value: 0 }));
+    }
+
+    #[test]
+    fn custom_constraint_is_used_in_search_and_validation() {
+        let thermometer = || Thermometer((0..4).map(|x| Coord::new(x, 1)).collect());
+        let board = RectangularBoard::new(2, 2).with_constraint(thermometer());
+        assert_eq!(board.constraints().count(), 13);
+        let solutions: Vec<RectangularBoard> = board.solutions().collect();
+        assert_eq!(solutions.len(), 12);
+        assert!(solutions.iter().all(|s| s.is_valid_solution() && s.to_line()[4..8] == *"1234"));
+
+        let plain = RectangularBoard::from_line("12343412....4321", 2, 2).unwrap().solutions().next().unwrap();
+        assert!(plain.is_valid_solution());
+        assert!(!plain.with_constraint(thermometer()).is_valid_solution());
+        let mut stuck = RectangularBoard::new(2, 2).with_constraint(thermometer());
+        stuck.board.prefill_value(&Coord::new(1, 1), 0).unwrap();
+        assert_eq!(stuck.solutions().count(), 0);
+        assert!(stuck.board.discover_new_values().is_err());
+    }
+
+    #[test]
+    fn custom_constraint_survives_rebuilding_the_board() {
+        let thermometer = Thermometer((0..4).map(|x| Coord::new(x, 1)).collect());
+        let board = RectangularBoard::new(2, 2).with_constraint(thermometer);
+        let killer = KillerBoard::new(&board, vec![cage(3, &[(2, 2), (3, 2)])]).unwrap();
+        assert_eq!(killer.constraints().count(), 15);
+        let solutions: Vec<KillerBoard> = killer.solutions().collect();
+        assert_eq!(solutions.len(), 4);
+        assert!(solutions.iter().all(|s| s.is_valid_solution() && s.to_line()[4..8] == *"1234"));
+
+        // Rebuilding a killer board replaces its cages, but keeps the thermometer.
+        let recaged = KillerBoard::new(&killer, vec![cage(3, &[(2, 0), (3, 0)])]).unwrap();
+        assert_eq!(recaged.cages.len(), 1);
+        assert_eq!(recaged.layout_groups().len(), 12);
+        assert_eq!(recaged.constraints().count(), 15);
+        let solutions: Vec<KillerBoard> = recaged.solutions().collect();
+        assert!(!solutions.is_empty());
+        assert!(solutions.iter().all(|s| s.to_line()[4..8] == *"1234"));
+
+        let diagonal = board.to_variant(Variant::Diagonal).unwrap();
+        assert_eq!(diagonal.constraints().count(), 15);
+        let solutions: Vec<RectangularBoard> = diagonal.solutions().collect();
+        assert!(!solutions.is_empty());
+        assert!(solutions.iter().all(|s| s.to_line()[4..8] == *"1234"));
+    }
+
+    /// Orthogonally adjacent cells may not hold consecutive values.
+    struct NonConsecutive;
+
+    impl NonConsecutive {
+        fn neighbours(coord: &Coord, group_size: usize) -> Vec<Coord> {
+            let mut neighbours = Vec::new();
+            if coord.x > 0 {
+                neighbours.push(Coord::new(coord.x - 1, coord.y));
+            }
+            if coord.y > 0 {
+                neighbours.push(Coord::new(coord.x, coord.y - 1));
+            }
+            if coord.x + 1 < group_size {
+                neighbours.push(Coord::new(coord.x + 1, coord.y));
+            }
+            if coord.y + 1 < group_size {
+                neighbours.push(Coord::new(coord.x, coord.y + 1));
+            }
+            neighbours
+        }
+    }
+
+    impl Constraint for NonConsecutive {
+        fn prune(&self, board: &mut Board) -> Result<bool> {
+            let group_size = board.group_size();
+            let mut pruned = false;
+            for coord in (0..group_size * group_size).map(|i| Coord::new(i % group_size, i / group_size)) {
+                if let Some(value) = board.get_cell(&coord).get_value() {
+                    for other in NonConsecutive::neighbours(&coord, group_size) {
+                        for excluded in [value.wrapping_sub(1), value + 1] {
+                            if board.get_cell(&other).is_possible(excluded) {
+                                board.strike_through(&other, excluded)?;
+                                pruned = true;
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(pruned)
+        }
+
+        fn is_satisfied(&self, board: &Board) -> bool {
+            let group_size = board.group_size();
+            (0..group_size * group_size).map(|i| Coord::new(i % group_size, i / group_size)).all(|coord| {
+                let value = board.get_cell(&coord).get_value();
+                NonConsecutive::neighbours(&coord, group_size).iter().all(|other| {
+                    let other = board.get_cell(other).get_value();
+                    value.zip(other).is_some_and(|(a, b)| a.abs_diff(b) != 1)
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn chess_rule_attacks() {
+        let knight = ChessRule::AntiKnight.attacked(&Coord::new(0, 0), 9);
+        assert_eq!(knight, vec![Coord::new(1, 2), Coord::new(2, 1)]);
+        assert_eq!(ChessRule::AntiKnight.attacked(&Coord::new(4, 4), 9).len(), 8);
+        assert_eq!(ChessRule::AntiKing.attacked(&Coord::new(8, 0), 9).len(), 3);
+        assert_eq!(ChessRule::from_name("AntiKing"), Some(ChessRule::AntiKing));
+        assert_eq!(ChessRule::from_name("anti-knight"), Some(ChessRule::AntiKnight));
+        assert_eq!(ChessRule::from_name("knight"), None);
+    }
+
+    #[test]
+    fn chess_rules_strike_attacked_cells() {
+        let mut board = RectangularBoard::new(3, 3)
+            .with_chess_rule(ChessRule::AntiKnight)
+            .with_chess_rule(ChessRule::AntiKing)
+            .with_chess_rule(ChessRule::AntiKnight);
+        assert_eq!(board.chess_rules(), &[ChessRule::AntiKnight, ChessRule::AntiKing]);
+        board.board.set_value(&Coord::new(2, 2), 4).unwrap();
+        board.board.discover_new_values().unwrap();
+        assert!(!board.get_cell(&Coord::new(3, 3)).is_possible(4));
+        assert!(!board.get_cell(&Coord::new(3, 4)).is_possible(4));
+        assert!(board.get_cell(&Coord::new(3, 5)).is_possible(4));
+        board.board.cells.get_mut_cell(&Coord::new(4, 3)).possible_values.retain_only(4);
+        assert_eq!(
+            ChessRule::AntiKnight.prune(&mut board.board),
+            Err(Error::Conflict { coord: Coord::new(4, 3), value: 4 })
+        );
+    }
+
+    #[test]
+    fn chess_rules_are_checked_in_validation() {
+        let solution = RectangularBoard::from_line(PUZZLE_LINE, 3, 3).unwrap().solutions().next().unwrap();
+        assert!(solution.is_valid_solution());
+        assert!(!solution.with_chess_rule(ChessRule::AntiKnight).is_valid_solution());
+        assert!(!solution.with_chess_rule(ChessRule::AntiKing).is_valid_solution());
+        let solutions: Vec<RectangularBoard> = RectangularBoard::new(2, 2)
+            .with_chess_rule(ChessRule::AntiKnight)
+            .solutions()
+            .collect();
+        assert_eq!(solutions.len(), 24);
+        assert!(solutions.iter().all(|s| s.is_valid_solution()));
+        // On a 4x4 board, the anti-king rule cannot be satisfied.
+        assert_eq!(RectangularBoard::new(2, 2).with_chess_rule(ChessRule::AntiKing).solutions().count(), 0);
+    }
+
+    #[test]
+    fn chess_rules_round_trip() {
+        let board = RectangularBoard::from_string("rule anti-king\nrule anti-knight\n5 5 1\n").unwrap();
+        assert_eq!(board.chess_rules(), &[ChessRule::AntiKing, ChessRule::AntiKnight]);
+        assert_eq!(board.to_triples(), "rule anti-king\nrule anti-knight\n5 5 1\n");
+        assert_eq!(board.to_variant(Variant::Diagonal).unwrap().chess_rules(), board.chess_rules());
+        assert_eq!(
+            RectangularBoard::from_string("rule anti-queen\n").err(),
+            Some(Error::Parse { line: 1, column: 6, message: String::from("unknown rule 'anti-queen'") })
+        );
+    }
+
+    const MIRACLE_SOLUTION: &str =
+        "483726159726159483159483726837261594261594837594837261372615948615948372948372615";
+
+    /// The givens are taken from the solution of the Miracle Sudoku below,
+    /// which has no repeated digit a knight's move apart.
+    #[test]
+    fn solve_anti_knight_puzzle() {
+        let puzzle = "........................726........................261..........1....37..483726.5";
+        let board = RectangularBoard::from_line(puzzle, 3, 3).unwrap();
+        assert_eq!(board.solutions().take(2).count(), 2);
+        let solutions: Vec<RectangularBoard> = board.with_chess_rule(ChessRule::AntiKnight).solutions().take(2).collect();
+        assert_eq!(solutions.len(), 1);
+        assert!(solutions[0].is_valid_solution());
+        assert_eq!(solutions[0].to_line(), MIRACLE_SOLUTION);
+    }
+
+    /// As above, for the anti-king rule.
+    #[test]
+    fn solve_anti_king_puzzle() {
+        let puzzle = "...............483...4................1...83....8...6..7..1......5..83.2..83.2.15";
+        let board = RectangularBoard::from_line(puzzle, 3, 3).unwrap();
+        assert_eq!(board.solutions().take(2).count(), 2);
+        let solutions: Vec<RectangularBoard> = board.with_chess_rule(ChessRule::AntiKing).solutions().take(2).collect();
+        assert_eq!(solutions.len(), 1);
+        assert!(solutions[0].is_valid_solution());
+        assert_eq!(solutions[0].to_line(), MIRACLE_SOLUTION);
+    }
+
+    /// The Miracle Sudoku by Mitchell Lee, with the anti-knight, anti-king
+    /// and non-consecutive rules, and only two givens.
+    #[test]
+    fn solve_miracle_sudoku() {
+        let mut board = RectangularBoard::new(3, 3)
+            .with_chess_rule(ChessRule::AntiKnight)
+            .with_chess_rule(ChessRule::AntiKing)
+            .with_constraint(NonConsecutive);
+        board.board.prefill_value(&Coord::new(2, 4), 0).unwrap();
+        board.board.prefill_value(&Coord::new(6, 5), 1).unwrap();
+        let solutions: Vec<RectangularBoard> = board.solutions().take(2).collect();
+        assert_eq!(solutions.len(), 1);
+        assert!(solutions[0].is_valid_solution());
+        assert_eq!(solutions[0].to_line(), MIRACLE_SOLUTION);
+    }
+
     #[test]
     fn solve_puzzle() {
         let mut board = RectangularBoard::new(3, 3);
-        board.board.prefill_value(&Coord::new(0, 0), 4);
-        board.board.prefill_value(&Coord::new(1, 0), 7);
-        board.board.prefill_value(&Coord::new(2, 0), 3);
-        board.board.prefill_value(&Coord::new(3, 0), 8);
-        board.board.prefill_value(&Coord::new(4, 0), 1);
-        board.board.prefill_value(&Coord::new(8, 0), 2);
-        board.board.prefill_value(&Coord::new(1, 1), 6);
-        board.board.prefill_value(&Coord::new(3, 1), 0);
-        board.board.prefill_value(&Coord::new(4, 1), 5);
-        board.board.prefill_value(&Coord::new(6, 1), 4);
-        board.board.prefill_value(&Coord::new(7, 1), 3);
-        board.board.prefill_value(&Coord::new(1, 2), 5);
-        board.board.prefill_value(&Coord::new(3, 2), 4);
-        board.board.prefill_value(&Coord::new(5, 2), 3);
-        board.board.prefill_value(&Coord::new(1, 3), 1);
-        board.board.prefill_value(&Coord::new(2, 3), 8);
-        board.board.prefill_value(&Coord::new(4, 3), 6);
-        board.board.prefill_value(&Coord::new(6, 3), 5);
-        board.board.prefill_value(&Coord::new(7, 3), 4);
-        board.board.prefill_value(&Coord::new(0, 4), 0);
-        board.board.prefill_value(&Coord::new(3, 4), 1);
-        board.board.prefill_value(&Coord::new(5, 4), 4);
-        board.board.prefill_value(&Coord::new(0, 5), 6);
-        board.board.prefill_value(&Coord::new(1, 5), 3);
-        board.board.prefill_value(&Coord::new(2, 5), 4);
-        board.board.prefill_value(&Coord::new(6, 5), 8);
-        board.board.prefill_value(&Coord::new(2, 6), 6);
-        board.board.prefill_value(&Coord::new(3, 6), 7);
-        board.board.prefill_value(&Coord::new(6, 6), 2);
-        board.board.prefill_value(&Coord::new(7, 6), 0);
-        board.board.prefill_value(&Coord::new(2, 7), 2);
-        board.board.prefill_value(&Coord::new(3, 7), 6);
-        board.board.prefill_value(&Coord::new(4, 7), 0);
-        board.board.prefill_value(&Coord::new(7, 7), 7);
-        board.board.prefill_value(&Coord::new(8, 7), 4);
-        board.board.prefill_value(&Coord::new(0, 8), 5);
-        board.board.prefill_value(&Coord::new(2, 8), 7);
-        board.board.prefill_value(&Coord::new(4, 8), 4);
-        board.board.prefill_value(&Coord::new(5, 8), 1);
-        board.board.prefill_value(&Coord::new(7, 8), 8);
-        board.board.prefill_value(&Coord::new(8, 8), 6);
+        board.board.prefill_value(&Coord::new(0, 0), 4).unwrap();
+        board.board.prefill_value(&Coord::new(1, 0), 7).unwrap();
+        board.board.prefill_value(&Coord::new(2, 0), 3).unwrap();
+        board.board.prefill_value(&Coord::new(3, 0), 8).unwrap();
+        board.board.prefill_value(&Coord::new(4, 0), 1).unwrap();
+        board.board.prefill_value(&Coord::new(8, 0), 2).unwrap();
+        board.board.prefill_value(&Coord::new(1, 1), 6).unwrap();
+        board.board.prefill_value(&Coord::new(3, 1), 0).unwrap();
+        board.board.prefill_value(&Coord::new(4, 1), 5).unwrap();
+        board.board.prefill_value(&Coord::new(6, 1), 4).unwrap();
+        board.board.prefill_value(&Coord::new(7, 1), 3).unwrap();
+        board.board.prefill_value(&Coord::new(1, 2), 5).unwrap();
+        board.board.prefill_value(&Coord::new(3, 2), 4).unwrap();
+        board.board.prefill_value(&Coord::new(5, 2), 3).unwrap();
+        board.board.prefill_value(&Coord::new(1, 3), 1).unwrap();
+        board.board.prefill_value(&Coord::new(2, 3), 8).unwrap();
+        board.board.prefill_value(&Coord::new(4, 3), 6).unwrap();
+        board.board.prefill_value(&Coord::new(6, 3), 5).unwrap();
+        board.board.prefill_value(&Coord::new(7, 3), 4).unwrap();
+        board.board.prefill_value(&Coord::new(0, 4), 0).unwrap();
+        board.board.prefill_value(&Coord::new(3, 4), 1).unwrap();
+        board.board.prefill_value(&Coord::new(5, 4), 4).unwrap();
+        board.board.prefill_value(&Coord::new(0, 5), 6).unwrap();
+        board.board.prefill_value(&Coord::new(1, 5), 3).unwrap();
+        board.board.prefill_value(&Coord::new(2, 5), 4).unwrap();
+        board.board.prefill_value(&Coord::new(6, 5), 8).unwrap();
+        board.board.prefill_value(&Coord::new(2, 6), 6).unwrap();
+        board.board.prefill_value(&Coord::new(3, 6), 7).unwrap();
+        board.board.prefill_value(&Coord::new(6, 6), 2).unwrap();
+        board.board.prefill_value(&Coord::new(7, 6), 0).unwrap();
+        board.board.prefill_value(&Coord::new(2, 7), 2).unwrap();
+        board.board.prefill_value(&Coord::new(3, 7), 6).unwrap();
+        board.board.prefill_value(&Coord::new(4, 7), 0).unwrap();
+        board.board.prefill_value(&Coord::new(7, 7), 7).unwrap();
+        board.board.prefill_value(&Coord::new(8, 7), 4).unwrap();
+        board.board.prefill_value(&Coord::new(0, 8), 5).unwrap();
+        board.board.prefill_value(&Coord::new(2, 8), 7).unwrap();
+        board.board.prefill_value(&Coord::new(4, 8), 4).unwrap();
+        board.board.prefill_value(&Coord::new(5, 8), 1).unwrap();
+        board.board.prefill_value(&Coord::new(7, 8), 8).unwrap();
+        board.board.prefill_value(&Coord::new(8, 8), 6).unwrap();
 
         assert_eq!(board.is_solved(), false);
 
@@ -345,33 +1895,33 @@ mod tests {
     #[test]
     fn solve_2_by_3_puzzle() {
         let mut board = RectangularBoard::new(2, 3);
-        board.board.prefill_value(&Coord::new(0, 0), 0);
-        board.board.prefill_value(&Coord::new(1, 0), 1);
-        board.board.prefill_value(&Coord::new(2, 0), 2);
-        board.board.prefill_value(&Coord::new(3, 0), 3);
-        board.board.prefill_value(&Coord::new(4, 0), 4);
-        board.board.prefill_value(&Coord::new(5, 0), 5);
-        board.board.prefill_value(&Coord::new(0, 1), 2);
-        board.board.prefill_value(&Coord::new(1, 1), 3);
-        board.board.prefill_value(&Coord::new(2, 1), 4);
-        board.board.prefill_value(&Coord::new(3, 1), 5);
-        board.board.prefill_value(&Coord::new(4, 1), 0);
-        board.board.prefill_value(&Coord::new(5, 1), 1);
-        board.board.prefill_value(&Coord::new(0, 2), 4);
-        board.board.prefill_value(&Coord::new(1, 2), 5);
-        board.board.prefill_value(&Coord::new(2, 2), 0);
-        board.board.prefill_value(&Coord::new(3, 2), 1);
-        board.board.prefill_value(&Coord::new(4, 2), 2);
-        board.board.prefill_value(&Coord::new(5, 2), 3);
-        board.board.prefill_value(&Coord::new(0, 3), 1);
-        board.board.prefill_value(&Coord::new(1, 3), 0);
-        board.board.prefill_value(&Coord::new(2, 3), 3);
-        board.board.prefill_value(&Coord::new(3, 3), 2);
-        board.board.prefill_value(&Coord::new(4, 3), 5);
-        board.board.prefill_value(&Coord::new(5, 3), 4);
-        board.board.prefill_value(&Coord::new(0, 4), 3);
-        board.board.prefill_value(&Coord::new(1, 4), 2);
-        board.board.prefill_value(&Coord::new(0, 5), 5);
+        board.board.prefill_value(&Coord::new(0, 0), 0).unwrap();
+        board.board.prefill_value(&Coord::new(1, 0), 1).unwrap();
+        board.board.prefill_value(&Coord::new(2, 0), 2).unwrap();
+        board.board.prefill_value(&Coord::new(3, 0), 3).unwrap();
+        board.board.prefill_value(&Coord::new(4, 0), 4).unwrap();
+        board.board.prefill_value(&Coord::new(5, 0), 5).unwrap();
+        board.board.prefill_value(&Coord::new(0, 1), 2).unwrap();
+        board.board.prefill_value(&Coord::new(1, 1), 3).unwrap();
+        board.board.prefill_value(&Coord::new(2, 1), 4).unwrap();
+        board.board.prefill_value(&Coord::new(3, 1), 5).unwrap();
+        board.board.prefill_value(&Coord::new(4, 1), 0).unwrap();
+        board.board.prefill_value(&Coord::new(5, 1), 1).unwrap();
+        board.board.prefill_value(&Coord::new(0, 2), 4).unwrap();
+        board.board.prefill_value(&Coord::new(1, 2), 5).unwrap();
+        board.board.prefill_value(&Coord::new(2, 2), 0).unwrap();
+        board.board.prefill_value(&Coord::new(3, 2), 1).unwrap();
+        board.board.prefill_value(&Coord::new(4, 2), 2).unwrap();
+        board.board.prefill_value(&Coord::new(5, 2), 3).unwrap();
+        board.board.prefill_value(&Coord::new(0, 3), 1).unwrap();
+        board.board.prefill_value(&Coord::new(1, 3), 0).unwrap();
+        board.board.prefill_value(&Coord::new(2, 3), 3).unwrap();
+        board.board.prefill_value(&Coord::new(3, 3), 2).unwrap();
+        board.board.prefill_value(&Coord::new(4, 3), 5).unwrap();
+        board.board.prefill_value(&Coord::new(5, 3), 4).unwrap();
+        board.board.prefill_value(&Coord::new(0, 4), 3).unwrap();
+        board.board.prefill_value(&Coord::new(1, 4), 2).unwrap();
+        board.board.prefill_value(&Coord::new(0, 5), 5).unwrap();
 
         assert_eq!(board.is_solved(), false);
 
@@ -385,7 +1935,7 @@ mod tests {
     #[test]
     fn solve_2_by_1_puzzle() {
         let mut board = RectangularBoard::new(2, 1);
-        board.board.prefill_value(&Coord::new(0, 0), 0);
+        board.board.prefill_value(&Coord::new(0, 0), 0).unwrap();
 
         assert_eq!(board.is_solved(), false);
 
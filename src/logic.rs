@@ -0,0 +1,245 @@
+//! Solving puzzles the way a human would, one named deduction at a time.
+//!
+//! The techniques only rely on the groups of a board, so they apply to any
+//! layout of groups, like NRC puzzles, and not just rows, columns and blocks.
+
+use super::board::Board;
+use super::coord::Coord;
+use super::error::{Error, Result};
+use super::techniques;
+
+/// The solving techniques, ordered from easiest to hardest.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Technique {
+    /// A value has only one possible cell in a group.
+    HiddenSingle,
+    /// A cell has only one possible value.
+    NakedSingle,
+    /// The places for a value in a block all lie in one line, so the value
+    /// is removed from the rest of that line.
+    PointingPair,
+    /// The places for a value in a line all lie in one block, so the value
+    /// is removed from the rest of that block.
+    BoxLineReduction,
+    NakedPair,
+    XWing,
+    HiddenPair,
+    NakedTriple,
+    Swordfish,
+    HiddenTriple,
+    XYWing,
+    NakedQuad,
+    HiddenQuad,
+    SimpleColouring,
+}
+
+impl Technique {
+    /// All techniques, in the order in which the solver tries them.
+    pub const ALL: [Technique; 14] = [
+        Technique::HiddenSingle,
+        Technique::NakedSingle,
+        Technique::PointingPair,
+        Technique::BoxLineReduction,
+        Technique::NakedPair,
+        Technique::XWing,
+        Technique::HiddenPair,
+        Technique::NakedTriple,
+        Technique::Swordfish,
+        Technique::HiddenTriple,
+        Technique::XYWing,
+        Technique::NakedQuad,
+        Technique::HiddenQuad,
+        Technique::SimpleColouring,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Technique::HiddenSingle => "Hidden single",
+            Technique::NakedSingle => "Naked single",
+            Technique::PointingPair => "Pointing pair",
+            Technique::BoxLineReduction => "Box/line reduction",
+            Technique::NakedPair => "Naked pair",
+            Technique::XWing => "X-Wing",
+            Technique::HiddenPair => "Hidden pair",
+            Technique::NakedTriple => "Naked triple",
+            Technique::Swordfish => "Swordfish",
+            Technique::HiddenTriple => "Hidden triple",
+            Technique::XYWing => "XY-Wing",
+            Technique::NakedQuad => "Naked quad",
+            Technique::HiddenQuad => "Hidden quad",
+            Technique::SimpleColouring => "Simple colouring",
+        }
+    }
+
+    /// The technique called `name`, ignoring case, spaces and punctuation,
+    /// so `x-wing` and `XWing` both give `Technique::XWing`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let simplify = |s: &str| -> String {
+            s.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_ascii_lowercase()
+        };
+        let name = simplify(name);
+        Technique::ALL.iter().find(|t| simplify(t.name()) == name).cloned()
+    }
+
+    /// Find a deduction on `board` using this technique.
+    pub fn find(&self, board: &Board) -> Option<Step> {
+        match self {
+            Technique::HiddenSingle => techniques::hidden_single(board),
+            Technique::NakedSingle => techniques::naked_single(board),
+            Technique::PointingPair | Technique::BoxLineReduction =>
+                techniques::intersection(board, *self),
+            Technique::NakedPair => techniques::naked_subset(board, 2, *self),
+            Technique::NakedTriple => techniques::naked_subset(board, 3, *self),
+            Technique::NakedQuad => techniques::naked_subset(board, 4, *self),
+            Technique::HiddenPair => techniques::hidden_subset(board, 2, *self),
+            Technique::HiddenTriple => techniques::hidden_subset(board, 3, *self),
+            Technique::HiddenQuad => techniques::hidden_subset(board, 4, *self),
+            Technique::XWing => techniques::fish(board, 2, *self),
+            Technique::Swordfish => techniques::fish(board, 3, *self),
+            Technique::XYWing => techniques::xy_wing(board),
+            Technique::SimpleColouring => techniques::simple_colouring(board),
+        }
+    }
+}
+
+/// A single deduction: values to place, or possible values to remove.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Step {
+    pub technique: Technique,
+    pub placements: Vec<(Coord, usize)>,
+    pub eliminations: Vec<(Coord, usize)>,
+    /// The cells the deduction is based on, like the corners of an X-Wing.
+    pub cells: Vec<Coord>,
+    /// Indices in `Board::groups` of the groups the deduction is based on.
+    pub groups: Vec<usize>,
+}
+
+impl Step {
+    pub fn placement(technique: Technique, coord: Coord, value: usize) -> Self {
+        Step {
+            technique,
+            placements: vec![(coord, value)],
+            eliminations: Vec::new(),
+            cells: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    pub fn elimination(technique: Technique, eliminations: Vec<(Coord, usize)>) -> Self {
+        Step {
+            technique,
+            placements: Vec::new(),
+            eliminations,
+            cells: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// Record the cells and groups this step is based on.
+    pub fn involving(mut self, cells: Vec<Coord>, groups: Vec<usize>) -> Self {
+        self.cells = cells;
+        self.groups = groups;
+        self
+    }
+}
+
+/// The result of solving a board with logical techniques only.
+pub struct LogicalSolution {
+    /// The board after applying all steps, solved unless the techniques got stuck.
+    pub board: Board,
+    pub steps: Vec<Step>,
+}
+
+impl LogicalSolution {
+    pub fn is_solved(&self) -> bool {
+        self.board.is_solved()
+    }
+
+    /// The hardest technique used, if any steps were needed.
+    pub fn hardest_technique(&self) -> Option<Technique> {
+        self.steps.iter().map(|s| s.technique).max()
+    }
+}
+
+/// Applies techniques to a board, easiest first, one step at a time.
+pub struct LogicalSolver {
+    board: Board,
+}
+
+impl LogicalSolver {
+    /// Start solving `board`.
+    ///
+    /// The givens of the puzzle are set first, removing them from the
+    /// possible values of their peers. Other cells with a single possible
+    /// value are left for the naked single technique, so they show up as
+    /// steps.
+    pub fn new(board: &Board) -> Result<Self> {
+        let mut board = board.clone();
+        let givens = board.cells.get_cell_coords_to_update().into_iter()
+            .filter(|coord| board.get_cell(coord).is_given)
+            .collect::<Vec<_>>();
+        for coord in givens {
+            match board.get_cell(&coord).get_value() {
+                Some(value) => board.set_value(&coord, value)?,
+                None => return Err(Error::NoCandidates { coord }),
+            }
+        }
+        Ok(LogicalSolver {
+            board,
+        })
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Find the easiest next step, without applying it.
+    pub fn next_step(&self) -> Option<Step> {
+        if self.board.is_solved() {
+            return None;
+        }
+        Technique::ALL.iter().find_map(|technique| technique.find(&self.board))
+    }
+
+    pub fn apply(&mut self, step: &Step) -> Result<()> {
+        for (coord, value) in &step.placements {
+            self.board.set_value(coord, *value)?;
+        }
+        for (coord, value) in &step.eliminations {
+            self.board.strike_through(coord, *value)?;
+        }
+        Ok(())
+    }
+
+    /// Apply steps until the board is solved, or no technique applies anymore.
+    pub fn solve(mut self) -> Result<LogicalSolution> {
+        let mut steps = Vec::new();
+        while let Some(step) = self.next_step() {
+            self.apply(&step)?;
+            steps.push(step);
+        }
+        Ok(LogicalSolution {
+            board: self.board,
+            steps,
+        })
+    }
+}
+
+impl Board {
+    /// Solve this board with logical techniques only, recording every step.
+    ///
+    /// This is the only solver that records steps: `discover_new_values`
+    /// and `set_value` change the board without a log.
+    pub fn solve_logically(&self) -> Result<LogicalSolution> {
+        LogicalSolver::new(self)?.solve()
+    }
+
+    /// The easiest deduction that can be made next, without solving the rest.
+    ///
+    /// The current possible values of the cells are taken as they are, so
+    /// values struck through by a player are not considered again. Returns
+    /// `None` when the board is solved, or when no technique applies.
+    pub fn hint(&self) -> Result<Option<Step>> {
+        Ok(LogicalSolver::new(self)?.next_step())
+    }
+}
@@ -1,37 +1,585 @@
-use sudoku::RectangularBoard;
-use std::fs;
+use sudoku::{ChessRule, DifficultyTarget, LogicalSolver, RectangularBoard, Symmetry, Technique, Variant};
+use sudoku::batch::{self, BatchOptions};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read};
+use std::process;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-fn main() {
-    let puzzle = fs::read_to_string("puzzle.txt")
-        .expect("Could not read puzzle.txt");
-    let board = RectangularBoard::from_string(&puzzle);
+const EXIT_NO_SOLUTION: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_INVALID_INPUT: i32 = 3;
+
+const USAGE: &str = "\
+Usage: sudoku <command> [options] [file]
+
+Commands:
+  solve       Print the solutions of a puzzle
+  count       Print the number of solutions of a puzzle
+  validate    Check that a puzzle has exactly one solution
+  convert     Write a puzzle in another format
+  batch       Solve a file with one puzzle per line, in the line format
+  explain     Print the logical steps solving a puzzle
+  hint        Print the easiest next logical step of a puzzle
+  grade       Rate the difficulty of a puzzle
+  generate    Create a puzzle with a unique solution, without reading input
+  minimize    Remove givens until the puzzle is minimal, keeping a unique solution
 
-    println!("Puzzle to solve:");
-    board.pretty_print();
+Options:
+  -f, --from <format>   Input format: auto, triples, line or grid (default: auto)
+  -t, --to <format>     Output format: pretty, triples, line or grid (default: pretty)
+                        explain, hint: text or json (default: text)
+  -s, --size <WxH>      Block width and height (default: inferred from the input,
+                        3x3 for generate)
+  -v, --variant <name>  Board variant: standard, nrc or diagonal (default: standard)
+  -r, --rule <name>     Chess rule: anti-knight or anti-king, may be repeated
+  -l, --limit <count>   solve: print at most this many solutions (default: 1)
+                        count: print <count>+ when there are more solutions
+                        batch: stop counting solutions at this count (default: 2)
+      --seed <number>   generate: seed of the random puzzle (default: random)
+      --symmetry <name> generate: symmetry of the givens: none, rotational180,
+                        rotational90, horizontal, vertical or diagonal (default: none)
+      --difficulty <name>
+                        generate: easy, medium, hard or expert (default: any)
+      --technique <name>
+                        generate: a technique the puzzle requires, like x-wing
+      --count <count>   generate: the number of puzzles (default: 1)
+      --time <seconds>  generate: stop after this many seconds (default: 60)
+  -h, --help            Print this help
 
-    let solutions = board.solve();
+The puzzle is read from `file`, or from stdin when `file` is absent or `-`.
 
-    assert_eq!(solutions.len(), 1);
-    assert_eq!(solutions.get(0).map(|it| it.is_solved()), Some(true));
+Exit status:
+  0  success
+  1  the puzzle has no solution, validate found no unique solution,
+     explain or hint found no logical step, or generate found too few puzzles
+  2  invalid command line
+  3  the puzzle could not be read";
 
-    println!("Solution:");
-    for s in solutions {
-        s.pretty_print();
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Command {
+    Solve,
+    Count,
+    Validate,
+    Convert,
+    Batch,
+    Explain,
+    Hint,
+    Grade,
+    Generate,
+    Minimize,
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "solve" => Some(Command::Solve),
+            "count" => Some(Command::Count),
+            "validate" => Some(Command::Validate),
+            "convert" => Some(Command::Convert),
+            "batch" => Some(Command::Batch),
+            "explain" => Some(Command::Explain),
+            "hint" => Some(Command::Hint),
+            "grade" => Some(Command::Grade),
+            "generate" => Some(Command::Generate),
+            "minimize" => Some(Command::Minimize),
+            _ => None,
+        }
     }
+}
 
-    for x in 1..6 {
-        for y in x..6 {
-            if x * y < 6 {
-                let board = RectangularBoard::new(x, y);
-                board.pretty_print();
-                let solutions = board.solve();
-                println!("-> has {} solutions", solutions.len());
-                let valid_solutions: Vec<&RectangularBoard> = solutions.iter()
-                    .filter(|s| { s.is_valid_solution() })
-                    .collect();
-                println!("   and {} valid solutions", valid_solutions.len());
-            }
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Format {
+    Auto,
+    Pretty,
+    Triples,
+    Line,
+    Grid,
+    Text,
+    Json,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Format::Auto),
+            "pretty" => Some(Format::Pretty),
+            "triples" => Some(Format::Triples),
+            "line" => Some(Format::Line),
+            "grid" => Some(Format::Grid),
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Reason to stop the program, with the exit status to report.
+struct Failure {
+    status: i32,
+    message: String,
+}
+
+impl Failure {
+    fn usage(message: String) -> Self {
+        Failure {
+            status: EXIT_USAGE,
+            message,
+        }
+    }
+
+    fn invalid_input(message: String) -> Self {
+        Failure {
+            status: EXIT_INVALID_INPUT,
+            message,
+        }
+    }
+}
+
+struct Options {
+    command: Command,
+    input: Option<String>,
+    from: Format,
+    to: Format,
+    block_size: Option<(usize, usize)>,
+    variant: Option<Variant>,
+    chess_rules: Vec<ChessRule>,
+    limit: Option<usize>,
+    seed: Option<u64>,
+    symmetry: Symmetry,
+    difficulty: Option<DifficultyTarget>,
+    technique: Option<Technique>,
+    count: usize,
+    time: Option<u64>,
+}
+
+fn parse_block_size(size: &str) -> Option<(usize, usize)> {
+    let mut parts = size.split('x').map(|n| n.parse::<usize>().ok());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Some(w)), Some(Some(h)), None) if w > 0 && h > 0 => Some((w, h)),
+        _ => None,
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, Failure> {
+    let mut args = args.iter();
+    let command = match args.next() {
+        Some(name) => Command::from_name(name)
+            .ok_or_else(|| Failure::usage(format!("unknown command '{}'", name)))?,
+        None => return Err(Failure::usage(String::from("missing command"))),
+    };
+    let mut options = Options {
+        command,
+        input: None,
+        from: Format::Auto,
+        to: Format::Pretty,
+        block_size: None,
+        variant: None,
+        chess_rules: Vec::new(),
+        limit: None,
+        seed: None,
+        symmetry: Symmetry::None,
+        difficulty: None,
+        technique: None,
+        count: 1,
+        time: None,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next()
+            .cloned()
+            .ok_or_else(|| Failure::usage(format!("missing value for {}", name)));
+        match arg.as_str() {
+            "-f" | "--from" => {
+                let name = value(arg)?;
+                options.from = match Format::from_name(&name) {
+                    Some(Format::Pretty) | Some(Format::Text) | Some(Format::Json) | None =>
+                        return Err(Failure::usage(format!("unknown input format '{}'", name))),
+                    Some(format) => format,
+                };
+            },
+            "-t" | "--to" => {
+                let name = value(arg)?;
+                let explains = command == Command::Explain || command == Command::Hint;
+                options.to = match Format::from_name(&name) {
+                    Some(format @ (Format::Text | Format::Json)) if explains => format,
+                    Some(format @ (Format::Pretty | Format::Triples | Format::Line | Format::Grid)) if !explains => format,
+                    _ => return Err(Failure::usage(format!("unknown output format '{}'", name))),
+                };
+            },
+            "-s" | "--size" => {
+                let size = value(arg)?;
+                options.block_size = Some(parse_block_size(&size)
+                    .ok_or_else(|| Failure::usage(format!("invalid block size '{}'", size)))?);
+            },
+            "-v" | "--variant" => {
+                let name = value(arg)?;
+                options.variant = Some(Variant::from_name(&name)
+                    .ok_or_else(|| Failure::usage(format!("unknown variant '{}'", name)))?);
+            },
+            "-r" | "--rule" => {
+                let name = value(arg)?;
+                options.chess_rules.push(ChessRule::from_name(&name)
+                    .ok_or_else(|| Failure::usage(format!("unknown rule '{}'", name)))?);
+            },
+            "-l" | "--limit" => {
+                let limit = value(arg)?;
+                options.limit = Some(limit.parse::<usize>().ok()
+                    .filter(|l| *l > 0)
+                    .ok_or_else(|| Failure::usage(format!("invalid limit '{}'", limit)))?);
+            },
+            "--seed" => {
+                let seed = value(arg)?;
+                options.seed = Some(seed.parse::<u64>()
+                    .map_err(|_| Failure::usage(format!("invalid seed '{}'", seed)))?);
+            },
+            "--symmetry" => {
+                let name = value(arg)?;
+                options.symmetry = Symmetry::from_name(&name)
+                    .ok_or_else(|| Failure::usage(format!("unknown symmetry '{}'", name)))?;
+            },
+            "--difficulty" => {
+                let name = value(arg)?;
+                options.difficulty = Some(DifficultyTarget::from_name(&name)
+                    .ok_or_else(|| Failure::usage(format!("unknown difficulty '{}'", name)))?);
+            },
+            "--technique" => {
+                let name = value(arg)?;
+                options.technique = Some(Technique::from_name(&name)
+                    .ok_or_else(|| Failure::usage(format!("unknown technique '{}'", name)))?);
+            },
+            "--count" => {
+                let count = value(arg)?;
+                options.count = count.parse::<usize>().ok()
+                    .filter(|c| *c > 0)
+                    .ok_or_else(|| Failure::usage(format!("invalid count '{}'", count)))?;
+            },
+            "--time" => {
+                let time = value(arg)?;
+                options.time = Some(time.parse::<u64>()
+                    .map_err(|_| Failure::usage(format!("invalid time '{}'", time)))?);
+            },
+            _ if arg.starts_with('-') && arg != "-" =>
+                return Err(Failure::usage(format!("unknown option '{}'", arg))),
+            _ if options.input.is_some() =>
+                return Err(Failure::usage(format!("unexpected argument '{}'", arg))),
+            _ => options.input = Some(arg.clone()),
+        }
+    }
+    Ok(options)
+}
+
+fn read_input(input: &Option<String>) -> Result<String, Failure> {
+    match input.as_deref() {
+        None | Some("-") => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)
+                .map_err(|e| Failure::invalid_input(format!("could not read stdin: {}", e)))?;
+            Ok(text)
+        },
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| Failure::invalid_input(format!("could not read {}: {}", path, e))),
+    }
+}
+
+/// Guess the format of `text`, based on the number of non-blank lines and their shape.
+fn detect_format(text: &str) -> Format {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    let is_triple = |l: &&str| {
+        let words: Vec<&str> = l.split_whitespace().collect();
+        words.len() == 3 && words.iter().all(|w| w.parse::<usize>().is_ok())
+    };
+    // Headers like `size 9x9` have one argument, `cage` headers a sum and cells.
+    let is_header = |l: &&str| l.trim_start().starts_with(char::is_alphabetic)
+        && l.split_whitespace().count() >= 2;
+    if lines.iter().all(|l| is_triple(l) || is_header(l)) {
+        Format::Triples
+    } else if lines.len() == 1 {
+        Format::Line
+    } else {
+        Format::Grid
+    }
+}
+
+/// The kind of puzzle in `text` that only the library reads: killer
+/// puzzles with `cage` headers, and jigsaw puzzles starting with a region
+/// map of letters followed by a blank line.
+fn library_only_kind(text: &str) -> Option<&'static str> {
+    if text.lines().any(|l| l.split_whitespace().next() == Some("cage")) {
+        return Some("killer");
+    }
+    let mut lines = text.lines().map(str::trim).skip_while(|l| l.is_empty());
+    let region_map: Vec<&str> = lines.by_ref().take_while(|l| !l.is_empty()).collect();
+    let has_puzzle = lines.any(|l| !l.is_empty());
+    if has_puzzle && region_map.len() > 1 && region_map.iter().all(|l| l.chars().all(char::is_alphabetic)) {
+        return Some("jigsaw");
+    }
+    None
+}
+
+/// Guess the block size of a square board written on a single line.
+fn line_block_size(line: &str) -> Option<(usize, usize)> {
+    let tokens = line.split_whitespace().count();
+    let cells = if tokens > 1 {
+        tokens
+    } else {
+        line.chars().count()
+    };
+    (1..=cells).find(|n| n * n * n * n == cells).map(|n| (n, n))
+}
+
+fn read_board(options: &Options) -> Result<RectangularBoard, Failure> {
+    let text = read_input(&options.input)?;
+    if let Some(kind) = library_only_kind(&text) {
+        return Err(Failure::invalid_input(format!(
+            "{} puzzles are not supported on the command line, only by the library", kind)));
+    }
+    let format = match options.from {
+        Format::Auto => detect_format(&text),
+        format => format,
+    };
+    let board = match format {
+        Format::Triples => {
+            let (w, h) = options.block_size.unwrap_or((3, 3));
+            RectangularBoard::from_string_with_defaults(&text, w, h, options.variant.unwrap_or(Variant::Standard))
+        },
+        Format::Line => {
+            let line = text.trim();
+            let (w, h) = options.block_size
+                .or_else(|| line_block_size(line))
+                .ok_or_else(|| Failure::invalid_input(String::from(
+                    "cannot infer the block size of the puzzle, use --size")))?;
+            RectangularBoard::from_line(line, w, h)
+        },
+        _ => RectangularBoard::from_grid(&text),
+    };
+    let board = board.map_err(|e| Failure::invalid_input(e.to_string()))?;
+    if let Some((w, h)) = options.block_size {
+        if (board.block_width(), board.block_height()) != (w, h) {
+            return Err(Failure::invalid_input(format!(
+                "the puzzle has {}x{} blocks, not {}x{}",
+                board.block_width(), board.block_height(), w, h)));
         }
     }
+    let board = match options.variant {
+        Some(variant) if variant != board.variant() => board.to_variant(variant)
+            .map_err(|e| Failure::invalid_input(e.to_string()))?,
+        _ => board,
+    };
+    Ok(with_chess_rules(board, options))
+}
+
+fn with_chess_rules(board: RectangularBoard, options: &Options) -> RectangularBoard {
+    options.chess_rules.iter().fold(board, |board, rule| board.with_chess_rule(*rule))
+}
+
+fn run_batch(options: &Options) -> Result<i32, Failure> {
+    let mut batch_options = BatchOptions::default();
+    if let Some((w, h)) = options.block_size {
+        batch_options.block_width = w;
+        batch_options.block_height = h;
+    }
+    if let Some(variant) = options.variant {
+        batch_options.variant = variant;
+    }
+    batch_options.chess_rules = options.chess_rules.clone();
+    if let Some(limit) = options.limit {
+        batch_options.solution_limit = limit;
+    }
+    let stdout = io::stdout();
+    let mut output = BufWriter::new(stdout.lock());
+    let summary = match options.input.as_deref() {
+        None | Some("-") => batch::solve_batch(io::stdin().lock(), &mut output, &batch_options),
+        Some(path) => {
+            let file = File::open(path)
+                .map_err(|e| Failure::invalid_input(format!("could not read {}: {}", path, e)))?;
+            batch::solve_batch(BufReader::new(file), &mut output, &batch_options)
+        },
+    }.map_err(|e| Failure::invalid_input(format!("batch failed: {}", e)))?;
+    eprintln!("{} puzzles: {} solved, {} unique, {} with multiple solutions, {} without solution, {} unreadable",
+              summary.puzzles, summary.solved, summary.unique, summary.multiple, summary.unsolvable, summary.errors);
+    Ok(0)
+}
+
+fn run_generate(options: &Options) -> Result<i32, Failure> {
+    let (w, h) = options.block_size.unwrap_or((3, 3));
+    let template = RectangularBoard::with_variant(w, h, options.variant.unwrap_or(Variant::Standard))
+        .map_err(|e| Failure::usage(e.to_string()))?;
+    let template = with_chess_rules(template, options);
+    let seed = options.seed.unwrap_or_else(|| SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64));
+    if options.seed.is_none() {
+        eprintln!("sudoku: seed {}", seed);
+    }
+    let mut target = options.difficulty.clone().unwrap_or_default();
+    target.technique = options.technique;
+    target.symmetry = options.symmetry;
+    let budget = Duration::from_secs(options.time.unwrap_or(60));
+    let report = options.difficulty.is_some() || options.technique.is_some();
+    let puzzles = template.generate_with_difficulty(seed, &target, options.count, budget, |progress| {
+        if report {
+            eprint!("\rsudoku: {} of {} puzzles found, {} generated in {:.1}s",
+                    progress.found, options.count, progress.attempts, progress.elapsed.as_secs_f64());
+        }
+    });
+    if report {
+        eprintln!();
+    }
+    for (index, (puzzle, _)) in puzzles.iter().enumerate() {
+        if index > 0 && options.to != Format::Line {
+            println!();
+        }
+        write_board(puzzle, options.to);
+    }
+    if puzzles.len() < options.count {
+        eprintln!("sudoku: found {} of {} puzzles within the time limit", puzzles.len(), options.count);
+        return Ok(EXIT_NO_SOLUTION);
+    }
+    Ok(0)
+}
+
+fn write_board(board: &RectangularBoard, format: Format) {
+    match format {
+        Format::Triples => print!("{}", board.to_triples()),
+        Format::Line => println!("{}", board.to_line()),
+        Format::Grid => print!("{}", board.to_grid()),
+        _ => board.pretty_print(),
+    }
 }
 
+fn run(args: &[String]) -> Result<i32, Failure> {
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return Ok(0);
+    }
+    let options = parse_options(args)?;
+    match options.command {
+        Command::Batch => return run_batch(&options),
+        Command::Generate => return run_generate(&options),
+        _ => (),
+    }
+    let board = read_board(&options)?;
+    match options.command {
+        Command::Solve => {
+            let limit = options.limit.unwrap_or(1);
+            let mut solutions = board.solutions();
+            let mut count = 0;
+            for solution in solutions.by_ref().take(limit) {
+                if count > 0 && options.to != Format::Line {
+                    println!();
+                }
+                write_board(&solution, options.to);
+                count += 1;
+            }
+            if count == 0 {
+                eprintln!("sudoku: the puzzle has no solution");
+                return Ok(EXIT_NO_SOLUTION);
+            }
+            if count == limit && solutions.next().is_some() {
+                eprintln!("sudoku: the puzzle has more solutions, use --limit to print more");
+            }
+        },
+        Command::Count => {
+            match options.limit {
+                Some(limit) => {
+                    // Count one more, to tell exactly `limit` solutions from more.
+                    let count = board.count_solutions_up_to(limit.saturating_add(1));
+                    if count > limit {
+                        println!("{}+", limit);
+                    } else {
+                        println!("{}", count);
+                    }
+                },
+                None => println!("{}", board.count_solutions()),
+            }
+        },
+        Command::Validate => {
+            match board.count_solutions_up_to(2) {
+                0 => {
+                    println!("invalid: the puzzle has no solution");
+                    return Ok(EXIT_NO_SOLUTION);
+                },
+                1 => println!("valid: the puzzle has a unique solution"),
+                _ => {
+                    println!("invalid: the puzzle has multiple solutions");
+                    return Ok(EXIT_NO_SOLUTION);
+                },
+            }
+        },
+        Command::Convert => write_board(&board, options.to),
+        Command::Minimize => {
+            match board.reduce_to_minimal() {
+                Some(minimal) => {
+                    let removed = board.givens().len() - minimal.givens().len();
+                    if removed == 0 {
+                        eprintln!("sudoku: the puzzle is already minimal");
+                    } else {
+                        eprintln!("sudoku: removed {} givens", removed);
+                    }
+                    write_board(&minimal, options.to);
+                },
+                None => {
+                    eprintln!("sudoku: the puzzle has no unique solution");
+                    return Ok(EXIT_NO_SOLUTION);
+                },
+            }
+        },
+        Command::Grade => {
+            let grade = board.grade()
+                .map_err(|e| Failure::invalid_input(e.to_string()))?;
+            println!("rating: {:.1}", grade.rating);
+            match grade.hardest_technique {
+                Some(technique) => println!("hardest technique: {} ({})", technique.name(), technique.family().name()),
+                None => println!("hardest technique: none"),
+            }
+            println!("logical steps: {}", grade.steps);
+            if grade.needs_guessing() {
+                println!("guesses: {}, guess depth: {}", grade.search.branches, grade.search.depth);
+                if grade.search.solutions == 0 {
+                    eprintln!("sudoku: the puzzle has no solution");
+                    return Ok(EXIT_NO_SOLUTION);
+                }
+            }
+        },
+        Command::Explain => {
+            let solution = board.solve_logically()
+                .map_err(|e| Failure::invalid_input(e.to_string()))?;
+            match options.to {
+                Format::Json => print!("{}", solution.to_json()),
+                _ => print!("{}", solution.explain()),
+            }
+            if !solution.is_solved() {
+                return Ok(EXIT_NO_SOLUTION);
+            }
+        },
+        Command::Hint => {
+            let solver = LogicalSolver::new(&board)
+                .map_err(|e| Failure::invalid_input(e.to_string()))?;
+            match (solver.next_step(), options.to) {
+                (Some(step), Format::Json) => println!("{}", step.to_json(&board)),
+                (Some(step), _) => println!("{}", step.describe(&board)),
+                (None, _) if solver.board().is_solved() => println!("the puzzle is solved"),
+                (None, _) => {
+                    eprintln!("sudoku: no technique applies to the puzzle");
+                    return Ok(EXIT_NO_SOLUTION);
+                },
+            }
+        },
+        Command::Batch | Command::Generate => unreachable!(),
+    }
+    Ok(0)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(status) => process::exit(status),
+        Err(failure) => {
+            eprintln!("sudoku: {}", failure.message);
+            if failure.status == EXIT_USAGE {
+                eprintln!("\n{}", USAGE);
+            }
+            process::exit(failure.status);
+        }
+    }
+}
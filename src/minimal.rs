@@ -0,0 +1,62 @@
+//! Checking whether a puzzle is minimal, and reducing puzzles to minimal ones.
+//!
+//! A puzzle is minimal when it has a unique solution, and removing any of
+//! its givens gives a puzzle with more solutions. Once removing a given
+//! breaks the uniqueness, it does so for every puzzle with fewer givens as
+//! well, so such givens never have to be tested again.
+
+use rayon::prelude::*;
+
+use super::board::Board;
+use super::coord::Coord;
+use super::RectangularBoard;
+
+impl Board {
+    /// Whether the puzzle of `givens` keeps a unique solution without the given at `coord`.
+    fn is_unique_without(&self, givens: &[(Coord, usize)], coord: &Coord) -> bool {
+        let remaining = givens.iter().filter(|(c, _)| c != coord);
+        self.with_givens(remaining).is_ok_and(|puzzle| puzzle.count_solutions_up_to(2) == 1)
+    }
+
+    /// Whether this puzzle has a unique solution, that is lost when any
+    /// of its givens is removed.
+    pub fn is_minimal(&self) -> bool {
+        let givens = self.givens();
+        self.count_solutions_up_to(2) == 1
+            && !givens.par_iter().any(|(c, _)| self.is_unique_without(&givens, c))
+    }
+
+    /// Remove givens from this puzzle until it is minimal, keeping its
+    /// unique solution. Returns `None` when this puzzle has no unique
+    /// solution.
+    ///
+    /// The first removable given, row by row, is removed first, so the
+    /// result only depends on the puzzle.
+    pub fn reduce_to_minimal(&self) -> Option<Board> {
+        if self.count_solutions_up_to(2) != 1 {
+            return None;
+        }
+        let mut givens = self.givens();
+        let mut candidates: Vec<Coord> = givens.iter().map(|(c, _)| c.clone()).collect();
+        loop {
+            let removable: Vec<Coord> = candidates.par_iter()
+                .filter(|c| self.is_unique_without(&givens, c))
+                .cloned()
+                .collect();
+            let first = match removable.first() {
+                Some(coord) => coord.clone(),
+                None => break,
+            };
+            givens.retain(|(c, _)| *c != first);
+            candidates = removable[1..].to_vec();
+        }
+        self.with_givens(givens.iter()).ok()
+    }
+}
+
+impl RectangularBoard {
+    /// Remove givens until this puzzle is minimal, see `Board::reduce_to_minimal`.
+    pub fn reduce_to_minimal(&self) -> Option<Self> {
+        self.board.reduce_to_minimal().map(|board| self.with_board(board))
+    }
+}
@@ -0,0 +1,70 @@
+//! Printing boards with outlined regions, like jigsaw regions and killer cages.
+
+use super::board::Board;
+use super::coord::Coord;
+use super::format::value_to_symbol;
+
+/// Print `board` in a grid, with borders around its regions. Once values
+/// are found, the givens are highlighted in brackets.
+///
+/// `region_of` gives the region of a cell, cells outside any region get
+/// `None`. A `label`, like the sum of a cage, is written in the border
+/// above its cell, so it should only be given for cells at the top of
+/// their region.
+pub fn pretty_print_outlined(
+    board: &Board,
+    region_of: impl Fn(&Coord) -> Option<usize>,
+    label: impl Fn(&Coord) -> Option<String>,
+) -> String {
+    let group_size = board.group_size();
+    // Whether there is a border above the cell at `x`, `y`, where `y` may
+    // be one past the last row.
+    let has_horizontal_border = |x: usize, y: usize| {
+        y == 0 || y == group_size || region_of(&Coord::new(x, y - 1)) != region_of(&Coord::new(x, y))
+    };
+    // Whether there is a border left of the cell at `x`, `y`, where `x`
+    // may be one past the last column.
+    let has_vertical_border = |x: usize, y: usize| {
+        x == 0 || x == group_size || region_of(&Coord::new(x - 1, y)) != region_of(&Coord::new(x, y))
+    };
+    let highlight_givens = (0..group_size)
+        .flat_map(|y| (0..group_size).map(move |x| Coord::new(x, y)))
+        .any(|c| !board.is_given(&c) && board.get_cell(&c).get_value().is_some());
+    let mut result = String::new();
+    for y in 0..=group_size {
+        for x in 0..=group_size {
+            let corner = (x > 0 && has_horizontal_border(x - 1, y))
+                || (x < group_size && has_horizontal_border(x, y))
+                || (y > 0 && has_vertical_border(x, y - 1))
+                || (y < group_size && has_vertical_border(x, y));
+            result.push(if corner { '+' } else { ' ' });
+            if x < group_size {
+                let border = match label(&Coord::new(x, y)) {
+                    Some(label) if y < group_size => format!("{:-<3}", label),
+                    _ if has_horizontal_border(x, y) => String::from("---"),
+                    _ => String::from("   "),
+                };
+                result.push_str(&border);
+            }
+        }
+        result.push('\n');
+        if y == group_size {
+            break;
+        }
+        for x in 0..=group_size {
+            result.push(if has_vertical_border(x, y) { '|' } else { ' ' });
+            if x < group_size {
+                let coord = Coord::new(x, y);
+                let v = match board.get_cell(&coord).get_value() {
+                    Some(v) if highlight_givens && board.is_given(&coord) =>
+                        format!("[{}]", value_to_symbol(Some(v), group_size)),
+                    Some(v) => format!(" {} ", value_to_symbol(Some(v), group_size)),
+                    None => String::from("   "),
+                };
+                result.push_str(&v);
+            }
+        }
+        result.push('\n');
+    }
+    result
+}
@@ -0,0 +1,80 @@
+use super::coord::Coord;
+use super::group::Group;
+use super::MAX_GROUP_SIZE;
+
+/// For every cell, the groups containing it and the other cells in those groups.
+///
+/// The index is built once per board layout, so placing a value does not
+/// need to scan all groups. The peers of all cells are stored in a single
+/// list of cell indices, as the index grows with the cube of `group_size`.
+#[derive(Debug)]
+pub struct PeerIndex {
+    group_size: usize,
+    groups_of: Vec<Vec<usize>>,
+    /// The peers of cell `i` are `peers[peer_starts[i]..peer_starts[i + 1]]`.
+    peers: Vec<u16>,
+    peer_starts: Vec<usize>,
+}
+
+impl PeerIndex {
+    /// Build the index of a board of `group_size`, which is at most
+    /// `MAX_GROUP_SIZE` so every cell index fits in a `u16`.
+    pub fn new(group_size: usize, groups: &[Group]) -> Self {
+        assert!(group_size <= MAX_GROUP_SIZE);
+        let cell_count = group_size * group_size;
+        let mut groups_of = vec![Vec::new(); cell_count];
+        for (group_index, group) in groups.iter().enumerate() {
+            for coord in &group.coordinates {
+                groups_of[coord.y * group_size + coord.x].push(group_index);
+            }
+        }
+        let mut peers = Vec::new();
+        let mut peer_starts = Vec::with_capacity(cell_count + 1);
+        let mut is_peer = vec![false; cell_count];
+        for (index, cell_groups) in groups_of.iter().enumerate() {
+            let start = peers.len();
+            peer_starts.push(start);
+            for coord in cell_groups.iter().flat_map(|g| &groups[*g].coordinates) {
+                let peer_index = coord.y * group_size + coord.x;
+                if peer_index != index && !is_peer[peer_index] {
+                    is_peer[peer_index] = true;
+                    peers.push(peer_index as u16);
+                }
+            }
+            for peer_index in &peers[start..] {
+                is_peer[*peer_index as usize] = false;
+            }
+        }
+        peer_starts.push(peers.len());
+        peers.shrink_to_fit();
+        PeerIndex {
+            group_size,
+            groups_of,
+            peers,
+            peer_starts,
+        }
+    }
+
+    fn index_of(&self, coord: &Coord) -> usize {
+        coord.y * self.group_size + coord.x
+    }
+
+    /// Indices of the groups containing `coord`.
+    pub fn groups_of(&self, coord: &Coord) -> &[usize] {
+        &self.groups_of[self.index_of(coord)]
+    }
+
+    /// The indices, counting row by row, of all other cells sharing a
+    /// group with `coord`.
+    pub fn peer_indices_of(&self, coord: &Coord) -> &[u16] {
+        let index = self.index_of(coord);
+        &self.peers[self.peer_starts[index]..self.peer_starts[index + 1]]
+    }
+
+    /// All other cells sharing a group with `coord`.
+    pub fn peers_of(&self, coord: &Coord) -> impl ExactSizeIterator<Item = Coord> + '_ {
+        let group_size = self.group_size;
+        self.peer_indices_of(coord).iter()
+            .map(move |peer| Coord::new(*peer as usize % group_size, *peer as usize / group_size))
+    }
+}
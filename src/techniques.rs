@@ -0,0 +1,391 @@
+//! The deductions behind each `Technique`.
+//!
+//! Every function looks for the first place on the board where its
+//! technique applies, and describes what follows from it as a `Step`.
+//! Only groups with a cell for every value take part, as the techniques
+//! rely on every value occurring exactly once in a group.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::board::Board;
+use super::candidates::Candidates;
+use super::coord::Coord;
+use super::group::Group;
+use super::logic::{Step, Technique};
+
+/// All combinations of `k` indices out of `0..n`, in lexicographic order.
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    fn extend(start: usize, n: usize, k: usize, current: &mut Vec<usize>, result: &mut Vec<Vec<usize>>) {
+        if current.len() == k {
+            result.push(current.clone());
+            return;
+        }
+        for i in start..n {
+            current.push(i);
+            extend(i + 1, n, k, current, result);
+            current.pop();
+        }
+    }
+    let mut result = Vec::new();
+    extend(0, n, k, &mut Vec::with_capacity(k), &mut result);
+    result
+}
+
+fn is_open(board: &Board, coord: &Coord) -> bool {
+    !board.get_cell(coord).is_set
+}
+
+fn is_line(group: &Group) -> bool {
+    let first = &group.coordinates[0];
+    group.coordinates.iter().all(|c| c.x == first.x)
+        || group.coordinates.iter().all(|c| c.y == first.y)
+}
+
+/// The groups taking part in the techniques, with their index on the board.
+fn houses(board: &Board) -> impl Iterator<Item = (usize, &Group)> {
+    let group_size = board.group_size();
+    board.groups.iter().enumerate()
+        .filter(move |(_, g)| g.coordinates.len() == group_size)
+}
+
+fn is_placed(board: &Board, group: &Group, value: usize) -> bool {
+    group.coordinates.iter().any(|c| {
+        let cell = board.get_cell(c);
+        cell.is_set && cell.get_value() == Some(value)
+    })
+}
+
+/// The open cells of `group` where `value` is still possible.
+fn positions(board: &Board, group: &Group, value: usize) -> Vec<Coord> {
+    group.coordinates.iter()
+        .filter(|c| is_open(board, c) && board.get_cell(c).is_possible(value))
+        .cloned()
+        .collect()
+}
+
+fn sees(board: &Board, a: &Coord, b: &Coord) -> bool {
+    board.peers_of(a).any(|peer| peer == *b)
+}
+
+/// Remove `value` from the open `coords` where it is still possible.
+fn eliminate_value<'a>(board: &Board, coords: impl Iterator<Item = &'a Coord>, value: usize) -> Vec<(Coord, usize)> {
+    coords
+        .filter(|c| is_open(board, c) && board.get_cell(c).is_possible(value))
+        .map(|c| (c.clone(), value))
+        .collect()
+}
+
+pub fn hidden_single(board: &Board) -> Option<Step> {
+    for (index, group) in houses(board) {
+        for value in 0..board.group_size() {
+            if is_placed(board, group, value) {
+                continue;
+            }
+            let positions = positions(board, group, value);
+            if positions.len() == 1 {
+                return Some(Step::placement(Technique::HiddenSingle, positions[0].clone(), value)
+                    .involving(positions, vec![index]));
+            }
+        }
+    }
+    None
+}
+
+pub fn naked_single(board: &Board) -> Option<Step> {
+    let group_size = board.group_size();
+    for y in 0..group_size {
+        for x in 0..group_size {
+            let coord = Coord::new(x, y);
+            let cell = board.get_cell(&coord);
+            if let (false, Some(value)) = (cell.is_set, cell.get_value()) {
+                return Some(Step::placement(Technique::NakedSingle, coord.clone(), value)
+                    .involving(vec![coord], Vec::new()));
+            }
+        }
+    }
+    None
+}
+
+/// Pointing pairs and box/line reductions: when all places for a value in
+/// one group lie in another group, the value is removed from the rest of
+/// the other group.
+pub fn intersection(board: &Board, technique: Technique) -> Option<Step> {
+    for (a, group) in houses(board) {
+        // Pointing starts from a block, box/line reduction from a line.
+        if is_line(group) != (technique == Technique::BoxLineReduction) {
+            continue;
+        }
+        for value in 0..board.group_size() {
+            let positions = positions(board, group, value);
+            if positions.len() < 2 || is_placed(board, group, value) {
+                continue;
+            }
+            for &b in board.groups_of(&positions[0]) {
+                let other = &board.groups[b];
+                if b == a || !positions.iter().all(|c| other.contains_coord(c)) {
+                    continue;
+                }
+                let outside = other.coordinates.iter().filter(|c| !group.contains_coord(c));
+                let eliminations = eliminate_value(board, outside, value);
+                if !eliminations.is_empty() {
+                    return Some(Step::elimination(technique, eliminations)
+                        .involving(positions, vec![a, b]));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// `size` cells of a group that together only have `size` possible values.
+pub fn naked_subset(board: &Board, size: usize, technique: Technique) -> Option<Step> {
+    for (index, group) in houses(board) {
+        let cells: Vec<&Coord> = group.coordinates.iter()
+            .filter(|c| is_open(board, c))
+            .filter(|c| (2..=size).contains(&board.get_cell(c).possibilities()))
+            .collect();
+        for combination in combinations(cells.len(), size) {
+            let mut values = Candidates::empty(board.group_size());
+            for &i in &combination {
+                values.union_with(&board.get_cell(cells[i]).possible_values);
+            }
+            if values.len() != size {
+                continue;
+            }
+            let subset: Vec<&Coord> = combination.iter().map(|&i| cells[i]).collect();
+            let eliminations: Vec<(Coord, usize)> = values.iter()
+                .flat_map(|value| {
+                    let others = group.coordinates.iter().filter(|c| !subset.contains(c));
+                    eliminate_value(board, others, value)
+                })
+                .collect();
+            if !eliminations.is_empty() {
+                let cells = subset.into_iter().cloned().collect();
+                return Some(Step::elimination(technique, eliminations).involving(cells, vec![index]));
+            }
+        }
+    }
+    None
+}
+
+/// `size` values of a group that together only have `size` possible cells.
+pub fn hidden_subset(board: &Board, size: usize, technique: Technique) -> Option<Step> {
+    for (index, group) in houses(board) {
+        let values: Vec<(usize, Vec<Coord>)> = (0..board.group_size())
+            .filter(|v| !is_placed(board, group, *v))
+            .map(|v| (v, positions(board, group, v)))
+            .filter(|(_, p)| (2..=size).contains(&p.len()))
+            .collect();
+        for combination in combinations(values.len(), size) {
+            let mut cells: Vec<&Coord> = Vec::new();
+            for &i in &combination {
+                for c in &values[i].1 {
+                    if !cells.contains(&c) {
+                        cells.push(c);
+                    }
+                }
+            }
+            if cells.len() != size {
+                continue;
+            }
+            let subset: Vec<usize> = combination.iter().map(|&i| values[i].0).collect();
+            let eliminations: Vec<(Coord, usize)> = cells.iter()
+                .flat_map(|c| {
+                    board.get_cell(c).possible_values.iter()
+                        .filter(|v| !subset.contains(v))
+                        .map(|v| ((*c).clone(), v))
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            if !eliminations.is_empty() {
+                let cells = cells.into_iter().cloned().collect();
+                return Some(Step::elimination(technique, eliminations).involving(cells, vec![index]));
+            }
+        }
+    }
+    None
+}
+
+fn are_disjoint(a: &Group, b: &Group) -> bool {
+    !a.coordinates.iter().any(|c| b.contains_coord(c))
+}
+
+/// X-Wing and Swordfish: when the places for a value in `size` disjoint
+/// base groups are covered by `size` disjoint cover groups, the value is
+/// removed from the rest of the cover groups.
+pub fn fish(board: &Board, size: usize, technique: Technique) -> Option<Step> {
+    let houses: Vec<(usize, &Group)> = houses(board).collect();
+    for value in 0..board.group_size() {
+        let bases: Vec<(usize, Vec<Coord>)> = houses.iter()
+            .filter(|(_, g)| !is_placed(board, g, value))
+            .map(|(i, g)| (*i, positions(board, g, value)))
+            .filter(|(_, p)| (2..=size).contains(&p.len()))
+            .collect();
+        for combination in combinations(bases.len(), size) {
+            let base_groups: Vec<&Group> = combination.iter().map(|&i| &board.groups[bases[i].0]).collect();
+            if !combination.iter().enumerate().all(|(n, &i)| {
+                combination[n + 1..].iter().all(|&j| are_disjoint(&board.groups[bases[i].0], &board.groups[bases[j].0]))
+            }) {
+                continue;
+            }
+            let cells: Vec<&Coord> = combination.iter().flat_map(|&i| &bases[i].1).collect();
+            let covers: Vec<(usize, &Group)> = houses.iter()
+                .filter(|(i, _)| !combination.iter().any(|&b| bases[b].0 == *i))
+                .filter(|(_, g)| cells.iter().any(|c| g.contains_coord(c)))
+                .cloned()
+                .collect();
+            for cover_combination in combinations(covers.len(), size) {
+                let cover_groups: Vec<&Group> = cover_combination.iter().map(|&i| covers[i].1).collect();
+                let covers_all = cells.iter().all(|c| cover_groups.iter().any(|g| g.contains_coord(c)));
+                let disjoint = cover_groups.iter().enumerate()
+                    .all(|(n, a)| cover_groups[n + 1..].iter().all(|b| are_disjoint(a, b)));
+                if !covers_all || !disjoint {
+                    continue;
+                }
+                let outside = cover_groups.iter()
+                    .flat_map(|g| &g.coordinates)
+                    .filter(|c| !base_groups.iter().any(|g| g.contains_coord(c)));
+                let eliminations = eliminate_value(board, outside, value);
+                if !eliminations.is_empty() {
+                    let groups = combination.iter().map(|&i| bases[i].0)
+                        .chain(cover_combination.iter().map(|&i| covers[i].0))
+                        .collect();
+                    let cells = cells.into_iter().cloned().collect();
+                    return Some(Step::elimination(technique, eliminations).involving(cells, groups));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// XY-Wing: a pivot with values `a` and `b` sees a pincer with `a` and `c`
+/// and a pincer with `b` and `c`. One of the pincers is `c`, so `c` is
+/// removed from all cells seeing both pincers.
+pub fn xy_wing(board: &Board) -> Option<Step> {
+    let group_size = board.group_size();
+    let pairs = |coord: &Coord| -> Option<(usize, usize)> {
+        let cell = board.get_cell(coord);
+        if cell.is_set || cell.possibilities() != 2 {
+            return None;
+        }
+        let mut values = cell.possible_values.iter();
+        Some((values.next()?, values.next()?))
+    };
+    for y in 0..group_size {
+        for x in 0..group_size {
+            let pivot = Coord::new(x, y);
+            let (a, b) = match pairs(&pivot) {
+                Some(pair) => pair,
+                None => continue,
+            };
+            let pincers: Vec<(Coord, usize, usize)> = board.peers_of(&pivot)
+                .filter_map(|c| pairs(&c).map(|(p, q)| (c, p, q)))
+                .collect();
+            for (first, p, q) in &pincers {
+                // The first pincer holds `a` and `c`.
+                let c = match (*p == a, *q == a) {
+                    (true, false) if *q != b => *q,
+                    (false, true) if *p != b => *p,
+                    _ => continue,
+                };
+                for (second, r, s) in &pincers {
+                    let matches = (*r == b && *s == c) || (*r == c && *s == b);
+                    if !matches || second == first {
+                        continue;
+                    }
+                    let targets: Vec<Coord> = board.peers_of(first)
+                        .filter(|t| t != second && sees(board, t, second))
+                        .collect();
+                    let eliminations = eliminate_value(board, targets.iter(), c);
+                    if !eliminations.is_empty() {
+                        let cells = vec![pivot.clone(), first.clone(), second.clone()];
+                        return Some(Step::elimination(Technique::XYWing, eliminations)
+                            .involving(cells, Vec::new()));
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Simple colouring: the cells of groups with exactly two places for a
+/// value form chains, in which the colours alternate between true and
+/// false. A colour that sees itself is false, and cells seeing both
+/// colours cannot hold the value.
+pub fn simple_colouring(board: &Board) -> Option<Step> {
+    for value in 0..board.group_size() {
+        let mut links: HashMap<Coord, Vec<Coord>> = HashMap::new();
+        let mut nodes: Vec<Coord> = Vec::new();
+        for (_, group) in houses(board) {
+            if is_placed(board, group, value) {
+                continue;
+            }
+            let positions = positions(board, group, value);
+            if positions.len() == 2 {
+                for (from, to) in [(0, 1), (1, 0)] {
+                    let entry = links.entry(positions[from].clone()).or_insert_with(|| {
+                        nodes.push(positions[from].clone());
+                        Vec::new()
+                    });
+                    entry.push(positions[to].clone());
+                }
+            }
+        }
+        let mut coloured: HashSet<Coord> = HashSet::new();
+        for start in &nodes {
+            if coloured.contains(start) {
+                continue;
+            }
+            let mut colours: [Vec<Coord>; 2] = [Vec::new(), Vec::new()];
+            let mut queue = VecDeque::new();
+            queue.push_back((start.clone(), 0));
+            coloured.insert(start.clone());
+            while let Some((coord, colour)) = queue.pop_front() {
+                for next in &links[&coord] {
+                    if coloured.insert(next.clone()) {
+                        queue.push_back((next.clone(), 1 - colour));
+                    }
+                }
+                colours[colour].push(coord);
+            }
+            if colours[1].is_empty() {
+                continue;
+            }
+            // Colour wrap: two cells of the same colour see each other.
+            for colour in &colours {
+                let wraps = colour.iter().enumerate()
+                    .any(|(n, a)| colour[n + 1..].iter().any(|b| sees(board, a, b)));
+                if wraps {
+                    let eliminations = eliminate_value(board, colour.iter(), value);
+                    let cells = colours.concat();
+                    return Some(Step::elimination(Technique::SimpleColouring, eliminations)
+                        .involving(cells, Vec::new()));
+                }
+            }
+            // Colour trap: a cell outside the chain sees both colours.
+            let group_size = board.group_size();
+            let mut eliminations = Vec::new();
+            for y in 0..group_size {
+                for x in 0..group_size {
+                    let coord = Coord::new(x, y);
+                    if colours.iter().any(|colour| colour.contains(&coord)) {
+                        continue;
+                    }
+                    let trapped = colours.iter()
+                        .all(|colour| colour.iter().any(|c| sees(board, &coord, c)));
+                    if trapped {
+                        eliminations.extend(eliminate_value(board, std::iter::once(&coord), value));
+                    }
+                }
+            }
+            if !eliminations.is_empty() {
+                let cells = colours.concat();
+                return Some(Step::elimination(Technique::SimpleColouring, eliminations)
+                    .involving(cells, Vec::new()));
+            }
+        }
+    }
+    None
+}